@@ -0,0 +1,41 @@
+//! Helpers shared by the benchmarks.
+
+use codec::Decode;
+use frame_metadata::{RuntimeMetadata, RuntimeMetadataPrefixed};
+use merkleized_metadata::ExtraInfo;
+
+/// All the fixtures.
+#[allow(dead_code)]
+pub const FIXTURES: &[&str] = &[
+	"rococo_metadata_v15",
+	"polkadot_metadata_v15",
+	"kusama_metadata_v15",
+	"acala_metadata_v15",
+	"moonbeam_metadata_v15",
+	"hydradx_metadata_v15",
+];
+
+/// Load the metadata of the given `fixture`.
+pub fn load_metadata(fixture: &str) -> RuntimeMetadata {
+	let metadata =
+		std::fs::read_to_string(format!("{}/fixtures/{fixture}", env!("CARGO_MANIFEST_DIR")))
+			.unwrap();
+
+	let metadata =
+		Option::<Vec<u8>>::decode(&mut &array_bytes::hex2bytes(metadata.trim_end()).unwrap()[..])
+			.unwrap()
+			.unwrap();
+
+	RuntimeMetadataPrefixed::decode(&mut &metadata[..]).unwrap().1
+}
+
+/// Some [`ExtraInfo`] for generating the digest.
+pub fn extra_info() -> ExtraInfo {
+	ExtraInfo {
+		spec_version: 1,
+		spec_name: "bench".into(),
+		base58_prefix: 0,
+		decimals: 10,
+		token_symbol: "DOT".into(),
+	}
+}
@@ -0,0 +1,41 @@
+//! Benchmarks for generating the metadata digest.
+//!
+//! Run them with and without `--features parallel` to compare the single threaded and the
+//! parallel implementation.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use merkleized_metadata::{generate_metadata_digest, generate_type_information, MerkleTree};
+
+mod common;
+use common::{extra_info, load_metadata};
+
+/// The biggest fixtures.
+const FIXTURES: &[&str] = &["polkadot_metadata_v15", "kusama_metadata_v15", "hydradx_metadata_v15"];
+
+fn digest(c: &mut Criterion) {
+	for fixture in FIXTURES {
+		let metadata = load_metadata(fixture);
+		let type_information = generate_type_information(&metadata).unwrap();
+
+		let mut group = c.benchmark_group(*fixture);
+
+		group.bench_function("generate_type_information", |b| {
+			b.iter(|| generate_type_information(&metadata).unwrap())
+		});
+		group.bench_function("MerkleTree::new", |b| {
+			b.iter_batched(
+				|| type_information.types.clone(),
+				MerkleTree::new,
+				BatchSize::LargeInput,
+			)
+		});
+		group.bench_function("generate_metadata_digest", |b| {
+			b.iter(|| generate_metadata_digest(&metadata, extra_info()).unwrap())
+		});
+
+		group.finish();
+	}
+}
+
+criterion_group!(benches, digest);
+criterion_main!(benches);
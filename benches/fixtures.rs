@@ -0,0 +1,116 @@
+//! Benchmarks for all the fixtures.
+//!
+//! Measures the individual steps of generating the digest and generating/verifying proofs for
+//! some representative extrinsics. The benchmarks of a proof report the encoded size of the proof
+//! as throughput in bytes, to also catch regressions of the proof sizes.
+
+use codec::{Compact, Encode};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use merkleized_metadata::{
+	generate_metadata_digest, generate_proof_for_extrinsic, verify_proof_with_digest,
+	FrameMetadataPrepared, MerkleTree, TypeId, TypeInformation,
+};
+
+mod common;
+use common::{extra_info, load_metadata, FIXTURES};
+
+/// Returns the encoded pallet and call index of `pallet.call`.
+fn call_index(type_information: &TypeInformation, pallet: &str, call: &str) -> Option<[u8; 2]> {
+	type_information
+		.lookup_call(pallet, call)
+		.map(|call| [call.pallet_index, call.call_index])
+}
+
+/// Returns the representative extrinsics for the given chain.
+///
+/// The extrinsics are unsigned, as the signed extensions are different for every chain.
+fn extrinsics(type_information: &TypeInformation) -> Vec<(&'static str, Vec<u8>)> {
+	let remark = call_index(type_information, "System", "remark").expect("Every chain has it");
+	let remark = |len: usize| (remark, vec![1u8; len]).encode();
+
+	let mut calls = vec![("remark", remark(32))];
+
+	if let Some(batch_all) = call_index(type_information, "Utility", "batch_all") {
+		let batch = (0..10).map(|i| remark(i * 10)).collect::<Vec<_>>();
+		calls.push((
+			"batch_all",
+			[&batch_all[..], &Compact(batch.len() as u32).encode(), &batch.concat()].concat(),
+		));
+	}
+
+	calls
+		.into_iter()
+		.map(|(name, call)| {
+			// An unsigned extrinsic of version 4.
+			let extrinsic = [&[4u8][..], &call].concat();
+			(name, extrinsic.encode())
+		})
+		.collect()
+}
+
+fn fixtures(c: &mut Criterion) {
+	for fixture in FIXTURES {
+		let metadata = load_metadata(fixture);
+		let prepared = FrameMetadataPrepared::prepare(&metadata).unwrap();
+		let type_information = prepared.as_type_information().unwrap();
+		let merkle_tree = MerkleTree::new(type_information.types.clone());
+		let extrinsics = extrinsics(&type_information);
+		let digest = generate_metadata_digest(&metadata, extra_info()).unwrap();
+
+		// `benches/digest.rs` uses the name of the fixture as group.
+		let mut group = c.benchmark_group(format!("{fixture}/suite"));
+
+		group.bench_function("prepare", |b| {
+			b.iter(|| FrameMetadataPrepared::prepare(&metadata).unwrap())
+		});
+		group.bench_function("as_type_information", |b| {
+			b.iter(|| prepared.as_type_information().unwrap())
+		});
+		group.bench_function("MerkleTree::new", |b| {
+			b.iter_batched(
+				|| type_information.types.clone(),
+				MerkleTree::new,
+				BatchSize::LargeInput,
+			)
+		});
+
+		for (name, extrinsic) in &extrinsics {
+			let proof = generate_proof_for_extrinsic(extrinsic, None, &metadata).unwrap();
+			group.throughput(Throughput::Bytes(proof.size_report().encoded_size as u64));
+
+			let type_ids = proof
+				.leaves
+				.iter()
+				.map(|leaf| match leaf.type_def.as_enumeration() {
+					Some(variant) =>
+						TypeId::Enumeration { type_id: leaf.type_id.0, variant: variant.index.0 },
+					None => TypeId::Other(leaf.type_id.0),
+				})
+				.collect::<Vec<_>>();
+
+			group.bench_function(format!("build_proof/{name}"), |b| {
+				b.iter(|| merkle_tree.build_proof(type_ids.iter().copied()).unwrap())
+			});
+			group.bench_function(format!("generate_proof_for_extrinsic/{name}"), |b| {
+				b.iter(|| generate_proof_for_extrinsic(extrinsic, None, &metadata).unwrap())
+			});
+			group.bench_function(format!("verify_proof_with_digest/{name}"), |b| {
+				b.iter(|| {
+					verify_proof_with_digest(
+						extrinsic,
+						None,
+						&type_information.extrinsic_metadata,
+						&digest,
+						&proof,
+					)
+					.unwrap()
+				})
+			});
+		}
+
+		group.finish();
+	}
+}
+
+criterion_group!(benches, fixtures);
+criterion_main!(benches);
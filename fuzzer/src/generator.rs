@@ -0,0 +1,209 @@
+//! Structure aware generation of extrinsics.
+//!
+//! Walks the [`TypeInformation`] to generate well-formed extrinsics and the additional signed
+//! data. The fuzzer input is only used to take the decisions, like which variant of an
+//! enumeration to use or how long a sequence should be. When the input is exhausted, every
+//! decision falls back to `0`. So, every input leads to an extrinsic, as long as the maximum
+//! nesting depth is not exceeded.
+
+use codec::{Compact, Encode};
+use merkleized_metadata::{
+    types::{Field, TypeDef, TypeRef},
+    TypeInformation,
+};
+
+/// The maximum nesting depth of types.
+///
+/// Protects against infinite recursion, e.g. a call that wraps a call.
+const MAX_DEPTH: usize = 32;
+
+/// The maximum length of generated sequences and strings.
+const MAX_SEQUENCE_LEN: usize = 16;
+
+/// The maximum number of bits in a generated bit sequence.
+const MAX_BITS: usize = 64;
+
+/// A generated extrinsic.
+pub struct GeneratedExtrinsic {
+    /// The encoded extrinsic, including the length prefix.
+    pub extrinsic: Vec<u8>,
+    /// The additional signed data, `Some(_)` if the extrinsic is signed.
+    pub additional_signed: Option<Vec<u8>>,
+}
+
+/// Generate an extrinsic using the decisions from `input`.
+///
+/// Returns `None` if the maximum nesting depth is exceeded.
+pub fn generate_extrinsic(
+    type_information: &TypeInformation,
+    input: &[u8],
+) -> Option<GeneratedExtrinsic> {
+    let mut generator = Generator {
+        type_information,
+        choices: Choices::new(input),
+        depth: 0,
+    };
+    let extrinsic_metadata = &type_information.extrinsic_metadata;
+
+    let signed = generator.choices.choose(2) == 1;
+
+    let mut extrinsic = Vec::new();
+    let additional_signed = if signed {
+        extrinsic.push(extrinsic_metadata.version | 0b1000_0000);
+        generator.generate(&extrinsic_metadata.address_ty, &mut extrinsic)?;
+        generator.generate(&extrinsic_metadata.signature_ty, &mut extrinsic)?;
+
+        let mut additional_signed = Vec::new();
+        for se in &extrinsic_metadata.signed_extensions {
+            generator.generate(&se.included_in_extrinsic, &mut extrinsic)?;
+            generator.generate(&se.included_in_signed_data, &mut additional_signed)?;
+        }
+
+        Some(additional_signed)
+    } else {
+        extrinsic.push(extrinsic_metadata.version);
+        None
+    };
+
+    generator.generate(&extrinsic_metadata.call_ty, &mut extrinsic)?;
+
+    Some(GeneratedExtrinsic {
+        extrinsic: extrinsic.encode(),
+        additional_signed,
+    })
+}
+
+/// The decisions taken from the fuzzer input.
+pub struct Choices<'a> {
+    input: &'a [u8],
+}
+
+impl<'a> Choices<'a> {
+    pub fn new(input: &'a [u8]) -> Self {
+        Self { input }
+    }
+
+    /// Returns `true` if the input is exhausted.
+    pub fn is_empty(&self) -> bool {
+        self.input.is_empty()
+    }
+
+    /// Returns the next byte of the input or `0` if the input is exhausted.
+    pub fn byte(&mut self) -> u8 {
+        match self.input.split_first() {
+            Some((byte, rest)) => {
+                self.input = rest;
+                *byte
+            }
+            None => 0,
+        }
+    }
+
+    /// Returns `N` bytes of the input.
+    pub fn bytes<const N: usize>(&mut self) -> [u8; N] {
+        core::array::from_fn(|_| self.byte())
+    }
+
+    /// Choose a value in `0..n`.
+    pub fn choose(&mut self, n: usize) -> usize {
+        if n <= 1 {
+            return 0;
+        }
+
+        u16::from_le_bytes(self.bytes()) as usize % n
+    }
+}
+
+struct Generator<'a> {
+    type_information: &'a TypeInformation,
+    choices: Choices<'a>,
+    depth: usize,
+}
+
+impl Generator<'_> {
+    /// Generate a value of `type_ref` and append its encoding to `out`.
+    fn generate(&mut self, type_ref: &TypeRef, out: &mut Vec<u8>) -> Option<()> {
+        match type_ref {
+            TypeRef::Bool => out.push(self.choices.choose(2) as u8),
+            TypeRef::Char => (b'a' as u32 + self.choices.choose(26) as u32).encode_to(out),
+            TypeRef::Str => {
+                let len = self.choices.choose(MAX_SEQUENCE_LEN + 1);
+                let string = (0..len)
+                    .map(|_| (b' ' + self.choices.choose(95) as u8) as char)
+                    .collect::<String>();
+                string.encode_to(out);
+            }
+            TypeRef::U8 | TypeRef::I8 => out.extend(self.choices.bytes::<1>()),
+            TypeRef::U16 | TypeRef::I16 => out.extend(self.choices.bytes::<2>()),
+            TypeRef::U32 | TypeRef::I32 => out.extend(self.choices.bytes::<4>()),
+            TypeRef::U64 | TypeRef::I64 => out.extend(self.choices.bytes::<8>()),
+            TypeRef::U128 | TypeRef::I128 => out.extend(self.choices.bytes::<16>()),
+            TypeRef::U256 | TypeRef::I256 => out.extend(self.choices.bytes::<32>()),
+            TypeRef::CompactU8 => Compact(u8::from_le_bytes(self.choices.bytes())).encode_to(out),
+            TypeRef::CompactU16 => Compact(u16::from_le_bytes(self.choices.bytes())).encode_to(out),
+            TypeRef::CompactU32 => Compact(u32::from_le_bytes(self.choices.bytes())).encode_to(out),
+            TypeRef::CompactU64 => Compact(u64::from_le_bytes(self.choices.bytes())).encode_to(out),
+            // The compact encoding of an `u128` is also a valid compact encoding of an `U256`.
+            TypeRef::CompactU128 | TypeRef::CompactU256 => {
+                Compact(u128::from_le_bytes(self.choices.bytes())).encode_to(out)
+            }
+            TypeRef::Void => {}
+            TypeRef::ById(id) => {
+                if self.depth >= MAX_DEPTH {
+                    return None;
+                }
+
+                self.depth += 1;
+                let res = self.generate_by_id(id.0, out);
+                self.depth -= 1;
+
+                return res;
+            }
+        }
+
+        Some(())
+    }
+
+    fn generate_by_id(&mut self, id: u32, out: &mut Vec<u8>) -> Option<()> {
+        let types = self
+            .type_information
+            .types_by_id(id)
+            .map(|(_, ty)| ty)
+            .collect::<Vec<_>>();
+
+        let ty = types.get(self.choices.choose(types.len()))?;
+
+        match &ty.type_def {
+            TypeDef::Enumeration(variant) => {
+                out.push(variant.index.0 as u8);
+                self.generate_fields(&variant.fields, out)
+            }
+            TypeDef::Composite(fields) => self.generate_fields(fields, out),
+            TypeDef::Sequence(type_ref) => {
+                let len = self.choices.choose(MAX_SEQUENCE_LEN + 1);
+                Compact(len as u32).encode_to(out);
+
+                (0..len).try_for_each(|_| self.generate(type_ref, out))
+            }
+            TypeDef::Array(array) => {
+                (0..array.len).try_for_each(|_| self.generate(&array.type_param, out))
+            }
+            TypeDef::Tuple(type_refs) => type_refs.iter().try_for_each(|t| self.generate(t, out)),
+            TypeDef::BitSequence(bit_sequence) => {
+                let bits = self.choices.choose(MAX_BITS + 1);
+                Compact(bits as u32).encode_to(out);
+
+                // The bits are stored in units of `num_bytes`.
+                let unit_bits = bit_sequence.num_bytes as usize * 8;
+                let num_bytes = bits.div_ceil(unit_bits) * bit_sequence.num_bytes as usize;
+                out.extend((0..num_bytes).map(|_| self.choices.byte()));
+
+                Some(())
+            }
+        }
+    }
+
+    fn generate_fields(&mut self, fields: &[Field], out: &mut Vec<u8>) -> Option<()> {
+        fields.iter().try_for_each(|f| self.generate(&f.ty, out))
+    }
+}
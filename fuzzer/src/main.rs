@@ -3,11 +3,20 @@ use codec::{Decode, Encode};
 use frame_metadata::{RuntimeMetadata, RuntimeMetadataPrefixed};
 use honggfuzz::fuzz;
 use merkleized_metadata::{
-    generate_metadata_digest, generate_proof_for_extrinsic,
-    types::{Hash, MetadataDigest, Type},
-    verify_proof, ExtraInfo, Proof,
+    generate_metadata_diff, generate_metadata_digest, generate_proof_for_extrinsic,
+    generate_type_information,
+    types::{ExtrinsicMetadata, Hash, MetadataDigest, Type},
+    verify_proof, verify_proof_with_digest, ExtraInfo, Proof,
 };
-use std::fs;
+use std::{
+    fs,
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+mod generator;
+mod mutator;
 
 #[derive(ValueEnum, Clone, Copy)]
 enum Method {
@@ -15,25 +24,57 @@ enum Method {
     FuzzMetadata,
     /// Load the metadata and only fuzz the extrinsic.
     LoadMetadata,
+    /// Load the metadata and generate valid extrinsics from it.
+    GenerateExtrinsic,
+    /// Generate valid extrinsics and mutate their proofs before verifying them.
+    FuzzProof,
+    /// Print the differences between the metadata of `--fixture` and `--new-fixture`.
+    Diff,
 }
 
 #[derive(Parser)]
 struct Cli {
     #[arg(long, value_enum, default_value_t = Method::LoadMetadata)]
     method: Method,
+    /// The fixture to load the metadata from.
+    #[arg(long, default_value = "rococo_metadata_v15")]
+    fixture: String,
+    /// The fixture to load the new metadata from, required by `diff`.
+    #[arg(long, required_if_eq("method", "diff"))]
+    new_fixture: Option<String>,
 }
 
 fn main() {
-    match Cli::parse().method {
+    let cli = Cli::parse();
+
+    match cli.method {
         Method::FuzzMetadata => {
             fuzz_metadata_and_extrinsic();
         }
         Method::LoadMetadata => {
-            fuzz_extrinsic_only();
+            fuzz_extrinsic_only(&cli.fixture);
+        }
+        Method::GenerateExtrinsic => {
+            fuzz_generated_extrinsic(&cli.fixture);
+        }
+        Method::FuzzProof => {
+            fuzz_proof(&cli.fixture);
+        }
+        Method::Diff => {
+            let new_fixture = cli.new_fixture.expect("Required for `diff`");
+            print_diff(&cli.fixture, &new_fixture);
         }
     }
 }
 
+/// Print the differences between the metadata of `old_fixture` and `new_fixture`.
+fn print_diff(old_fixture: &str, new_fixture: &str) {
+    let diff =
+        generate_metadata_diff(&load_metadata(old_fixture), &load_metadata(new_fixture)).unwrap();
+
+    println!("{diff:#?}");
+}
+
 /// Fuzz the metadata and the extrinsic.
 fn fuzz_metadata_and_extrinsic() {
     loop {
@@ -49,11 +90,11 @@ fn fuzz_metadata_and_extrinsic() {
     }
 }
 
-/// Fuzz the extrinsic only.
-fn fuzz_extrinsic_only() {
+/// Load the metadata from the given `fixture`.
+fn load_metadata(fixture: &str) -> RuntimeMetadata {
     let metadata = String::from_utf8(
         fs::read(format!(
-            "{}/../fixtures/rococo_metadata_v15",
+            "{}/../fixtures/{fixture}",
             env!("CARGO_MANIFEST_DIR")
         ))
         .unwrap(),
@@ -66,9 +107,14 @@ fn fuzz_extrinsic_only() {
     .unwrap()
     .unwrap();
 
-    let metadata = RuntimeMetadataPrefixed::decode(&mut &metadata[..])
+    RuntimeMetadataPrefixed::decode(&mut &metadata[..])
         .unwrap()
-        .1;
+        .1
+}
+
+/// Fuzz the extrinsic only.
+fn fuzz_extrinsic_only(fixture: &str) {
+    let metadata = load_metadata(fixture);
 
     loop {
         fuzz!(|data: (&[u8], Option<Vec<u8>>)| {
@@ -79,6 +125,151 @@ fn fuzz_extrinsic_only() {
     }
 }
 
+/// Fuzz using extrinsics that are generated from the metadata.
+///
+/// The fuzzer input is used to take the decisions while generating the extrinsic.
+fn fuzz_generated_extrinsic(fixture: &str) {
+    let metadata = load_metadata(fixture);
+    let type_information = generate_type_information(&metadata).unwrap();
+
+    loop {
+        fuzz!(|data: &[u8]| {
+            let Some(generated) = generator::generate_extrinsic(&type_information, data) else {
+                // Nested too deep, let's skip it.
+                return;
+            };
+
+            let proof = generate_proof_for_extrinsic(
+                &generated.extrinsic,
+                generated.additional_signed.as_deref(),
+                &metadata,
+            )
+            .unwrap_or_else(|e| {
+                panic!(
+                    "Failed to generate proof for generated extrinsic `{}`: {e}",
+                    array_bytes::bytes2hex("0x", &generated.extrinsic)
+                )
+            });
+
+            verify_proof_and_root(
+                &metadata,
+                &generated.extrinsic,
+                generated.additional_signed,
+                &proof,
+            );
+        });
+    }
+}
+
+/// Fuzz the proof that is passed to the verifier.
+///
+/// The verifier must never panic, never accept a proof whose root differs from the root in the
+/// digest and never loop. The root of an accepted proof is recomputed by [`proof_root_hash`],
+/// independently of the verifier. The verification runs on a separate thread and the fuzzer
+/// panics if it doesn't finish within [`VERIFICATION_TIMEOUT`].
+fn fuzz_proof(fixture: &str) {
+    let metadata = load_metadata(fixture);
+    let type_information = generate_type_information(&metadata).unwrap();
+    let digest = generate_metadata_digest(&metadata, extra_info()).unwrap();
+    let MetadataDigest::V1 {
+        types_tree_root, ..
+    } = digest
+    else {
+        panic!("Invalid digest")
+    };
+
+    loop {
+        fuzz!(|data: (&[u8], &[u8])| {
+            let (extrinsic, mutations) = data;
+
+            let Some(generated) = generator::generate_extrinsic(&type_information, extrinsic)
+            else {
+                return;
+            };
+
+            let mut proof = generate_proof_for_extrinsic(
+                &generated.extrinsic,
+                generated.additional_signed.as_deref(),
+                &metadata,
+            )
+            .unwrap();
+
+            verify_with_timeout(
+                &generated,
+                &type_information.extrinsic_metadata,
+                &digest,
+                &proof,
+            )
+            .unwrap();
+
+            mutator::mutate_proof(&mut proof, mutations);
+
+            if verify_with_timeout(
+                &generated,
+                &type_information.extrinsic_metadata,
+                &digest,
+                &proof,
+            )
+            .is_ok()
+            {
+                assert_eq!(
+                    types_tree_root,
+                    proof_root_hash(&proof),
+                    "Accepted proof with wrong root"
+                );
+            }
+        });
+    }
+}
+
+/// The time after which the verification of a proof is considered to loop.
+const VERIFICATION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Verify `proof` for the `generated` extrinsic on a separate thread.
+///
+/// Panics if the verification doesn't finish within [`VERIFICATION_TIMEOUT`].
+fn verify_with_timeout(
+    generated: &generator::GeneratedExtrinsic,
+    extrinsic_metadata: &ExtrinsicMetadata,
+    digest: &MetadataDigest,
+    proof: &Proof,
+) -> Result<(), String> {
+    let (sender, receiver) = mpsc::channel();
+    let (extrinsic, additional_signed) = (
+        generated.extrinsic.clone(),
+        generated.additional_signed.clone(),
+    );
+    let (extrinsic_metadata, digest, proof) =
+        (extrinsic_metadata.clone(), digest.clone(), proof.clone());
+
+    thread::spawn(move || {
+        let _ = sender.send(verify_proof_with_digest(
+            &extrinsic,
+            additional_signed.as_deref(),
+            &extrinsic_metadata,
+            &digest,
+            &proof,
+        ));
+    });
+
+    match receiver.recv_timeout(VERIFICATION_TIMEOUT) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => panic!("Verification of the proof doesn't terminate"),
+        Err(RecvTimeoutError::Disconnected) => panic!("Verification of the proof panicked"),
+    }
+}
+
+/// The [`ExtraInfo`] used for generating the digest.
+fn extra_info() -> ExtraInfo {
+    ExtraInfo {
+        spec_version: 1,
+        spec_name: "fuzz".into(),
+        base58_prefix: 42,
+        decimals: 10,
+        token_symbol: "fuzz".into(),
+    }
+}
+
 fn generate_proof_and_verify(
     metadata: &RuntimeMetadata,
     extrinsic: &[u8],
@@ -93,22 +284,21 @@ fn generate_proof_and_verify(
 
     println!("Generated valid extrinsic");
 
+    verify_proof_and_root(metadata, extrinsic, additional_signed, &proof);
+}
+
+/// Verify that `proof` can decode the `extrinsic` and that its root matches the `metadata`.
+fn verify_proof_and_root(
+    metadata: &RuntimeMetadata,
+    extrinsic: &[u8],
+    additional_signed: Option<Vec<u8>>,
+    proof: &Proof,
+) {
     verify_proof(extrinsic, additional_signed.as_deref(), &metadata, &proof).unwrap();
 
     let proof_root = proof_root_hash(&proof);
 
-    let metadata_root = match generate_metadata_digest(
-        &metadata,
-        ExtraInfo {
-            spec_version: 1,
-            spec_name: "fuzz".into(),
-            base58_prefix: 42,
-            decimals: 10,
-            token_symbol: "fuzz".into(),
-        },
-    )
-    .unwrap()
-    {
+    let metadata_root = match generate_metadata_digest(&metadata, extra_info()).unwrap() {
         MetadataDigest::V1 {
             types_tree_root, ..
         } => types_tree_root,
@@ -0,0 +1,86 @@
+//! Mutation of proofs.
+//!
+//! Simulates an attacker that controls the proof that is sent to a signer.
+
+use crate::generator::Choices;
+use codec::Compact;
+use merkleized_metadata::Proof;
+
+/// The maximum number of mutations applied to one proof.
+const MAX_MUTATIONS: usize = 16;
+
+/// Mutate `proof` using the decisions from `input`.
+pub fn mutate_proof(proof: &mut Proof, input: &[u8]) {
+    let mut choices = Choices::new(input);
+
+    for _ in 0..MAX_MUTATIONS {
+        if choices.is_empty() {
+            return;
+        }
+
+        match choices.byte() % 11 {
+            // Flip a bit of a node hash.
+            0 if !proof.nodes.is_empty() => {
+                let node = choices.choose(proof.nodes.len());
+                let byte = choices.choose(32);
+                proof.nodes[node][byte] ^= 1 << (choices.byte() % 8);
+            }
+            // Remove a node hash.
+            1 if !proof.nodes.is_empty() => {
+                proof.nodes.remove(choices.choose(proof.nodes.len()));
+            }
+            // Duplicate a node hash.
+            2 if !proof.nodes.is_empty() => {
+                let node = choices.choose(proof.nodes.len());
+                proof.nodes.insert(node, proof.nodes[node]);
+            }
+            // Truncate the node hashes.
+            3 => proof.nodes.truncate(choices.choose(proof.nodes.len() + 1)),
+            // Remove a leaf with its index.
+            4 if !proof.leaves.is_empty() => {
+                let leaf = choices.choose(proof.leaves.len());
+                proof.leaves.remove(leaf);
+                if leaf < proof.leaf_indices.len() {
+                    proof.leaf_indices.remove(leaf);
+                }
+            }
+            // Duplicate a leaf with its index.
+            5 if !proof.leaves.is_empty() => {
+                let leaf = choices.choose(proof.leaves.len());
+                proof.leaves.insert(leaf, proof.leaves[leaf].clone());
+                if let Some(index) = proof.leaf_indices.get(leaf).copied() {
+                    proof.leaf_indices.insert(leaf, index);
+                }
+            }
+            // Swap two leaves, but keep the indices.
+            6 if !proof.leaves.is_empty() => {
+                let a = choices.choose(proof.leaves.len());
+                let b = choices.choose(proof.leaves.len());
+                proof.leaves.swap(a, b);
+            }
+            // Change a leaf index.
+            7 if !proof.leaf_indices.is_empty() => {
+                let leaf = choices.choose(proof.leaf_indices.len());
+                proof.leaf_indices[leaf] = u32::from_le_bytes(choices.bytes());
+            }
+            // Change the type id of a leaf.
+            8 if !proof.leaves.is_empty() => {
+                let leaf = choices.choose(proof.leaves.len());
+                proof.leaves[leaf].type_id = Compact(u32::from_le_bytes(choices.bytes()));
+            }
+            // Change the path of a leaf.
+            9 if !proof.leaves.is_empty() => {
+                let leaf = choices.choose(proof.leaves.len());
+                let path = &mut proof.leaves[leaf].path;
+                if path.pop().is_none() {
+                    path.push("Fuzz".into());
+                }
+            }
+            // Truncate the leaf indices.
+            10 => proof
+                .leaf_indices
+                .truncate(choices.choose(proof.leaf_indices.len() + 1)),
+            _ => {}
+        }
+    }
+}
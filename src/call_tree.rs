@@ -0,0 +1,322 @@
+//! The tree of the calls in an extrinsic.
+//!
+//! Calls like `Utility.batch_all`, `Proxy.proxy`, `Multisig.as_multi` or `Sudo.sudo` contain other
+//! calls. These nested calls are detected while decoding, as they are encoded as values of the
+//! [`call_ty`](crate::types::ExtrinsicMetadata::call_ty).
+
+use alloc::{
+	string::{String, ToString},
+	vec::Vec,
+};
+use core::ops::Range;
+use scale_decode::{
+	visitor::{decode_with_visitor, DecodeAsTypeResult, DecodeError},
+	Visitor,
+};
+
+use crate::{
+	extrinsic_decoder::{
+		decode_compact_u256, decode_extrinsic_parts, decode_items, decode_with, TypeResolver,
+	},
+	types::{ExtrinsicMetadata, Type, TypeRef},
+};
+
+/// A call in the call tree of an extrinsic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallNode {
+	/// The name of the pallet.
+	pub pallet: String,
+	/// The name of the call.
+	pub call: String,
+	/// The range of the encoded call in the extrinsic.
+	pub range: Range<usize>,
+	/// The nesting depth, `0` for the call of the extrinsic.
+	pub depth: usize,
+}
+
+/// Decode the given `extrinsic` and collect its call tree.
+pub(crate) fn collect_call_tree<'a>(
+	extrinsic: &mut &[u8],
+	extrinsic_metadata: &ExtrinsicMetadata,
+	types: impl Iterator<Item = &'a Type>,
+) -> Result<Vec<CallNode>, String> {
+	let type_resolver = TypeResolver::new(types);
+	let visitor = CollectCalls::new(extrinsic_metadata.call_ty, extrinsic.len());
+
+	let (visitor, _) = decode_extrinsic_parts(
+		extrinsic,
+		extrinsic_metadata,
+		visitor,
+		decode_with(&type_resolver),
+	)?;
+
+	Ok(visitor.calls)
+}
+
+/// The name of a call that still needs to be set.
+#[derive(Clone, Copy)]
+enum Naming {
+	/// The next variant is the pallet of the call at the given index.
+	Pallet(usize),
+	/// The next variant is the call at the given index.
+	Call(usize),
+}
+
+/// Visitor that collects the [`CallNode`]s.
+struct CollectCalls {
+	call_ty: TypeRef,
+	/// The length of the input, required to calculate the offsets.
+	input_len: usize,
+	calls: Vec<CallNode>,
+	depth: usize,
+	naming: Option<Naming>,
+	/// Set when entering a call, to not start the same call again.
+	entering_call: bool,
+}
+
+impl CollectCalls {
+	fn new(call_ty: TypeRef, input_len: usize) -> Self {
+		Self { call_ty, input_len, calls: Vec::new(), depth: 0, naming: None, entering_call: false }
+	}
+
+	/// Returns the offset of `input` in the input.
+	fn offset(&self, input: &[u8]) -> usize {
+		self.input_len - input.len()
+	}
+
+	/// Decode the call at the start of `input` and its nested calls.
+	fn decode_call(mut self, input: &mut &[u8], types: &TypeResolver) -> Result<Self, DecodeError> {
+		let (depth, naming) = (self.depth, self.naming);
+		let index = self.calls.len();
+		let start = self.offset(input);
+
+		self.calls.push(CallNode {
+			pallet: String::new(),
+			call: String::new(),
+			range: start..start,
+			depth,
+		});
+		self.depth += 1;
+		self.naming = Some(Naming::Pallet(index));
+		self.entering_call = true;
+
+		let call_ty = self.call_ty;
+		let mut visitor = decode_with_visitor(input, call_ty, types, self)?;
+
+		visitor.calls[index].range.end = visitor.offset(input);
+		visitor.depth = depth;
+		visitor.naming = naming;
+
+		Ok(visitor)
+	}
+}
+
+impl Visitor for CollectCalls {
+	type TypeResolver = TypeResolver;
+	type Value<'scale, 'resolver> = Self;
+	type Error = DecodeError;
+
+	fn unchecked_decode_as_type<'scale, 'resolver>(
+		mut self,
+		input: &mut &'scale [u8],
+		type_id: TypeRef,
+		types: &'resolver TypeResolver,
+	) -> DecodeAsTypeResult<Self, Result<Self::Value<'scale, 'resolver>, Self::Error>> {
+		if type_id == TypeRef::CompactU256 {
+			DecodeAsTypeResult::Decoded(
+				decode_compact_u256(input).map(|_| self).map_err(Into::into),
+			)
+		} else if type_id == self.call_ty && !self.entering_call {
+			DecodeAsTypeResult::Decoded(self.decode_call(input, types))
+		} else {
+			self.entering_call = false;
+			DecodeAsTypeResult::Skipped(self)
+		}
+	}
+
+	fn visit_bool<'scale, 'resolver>(
+		self,
+		_value: bool,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(self)
+	}
+
+	fn visit_char<'scale, 'resolver>(
+		self,
+		_value: char,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(self)
+	}
+
+	fn visit_u8<'scale, 'resolver>(
+		self,
+		_value: u8,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(self)
+	}
+
+	fn visit_u16<'scale, 'resolver>(
+		self,
+		_value: u16,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(self)
+	}
+
+	fn visit_u32<'scale, 'resolver>(
+		self,
+		_value: u32,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(self)
+	}
+
+	fn visit_u64<'scale, 'resolver>(
+		self,
+		_value: u64,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(self)
+	}
+
+	fn visit_u128<'scale, 'resolver>(
+		self,
+		_value: u128,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(self)
+	}
+
+	fn visit_u256<'resolver>(
+		self,
+		_value: &[u8; 32],
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'_, 'resolver>, Self::Error> {
+		Ok(self)
+	}
+
+	fn visit_i8<'scale, 'resolver>(
+		self,
+		_value: i8,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(self)
+	}
+
+	fn visit_i16<'scale, 'resolver>(
+		self,
+		_value: i16,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(self)
+	}
+
+	fn visit_i32<'scale, 'resolver>(
+		self,
+		_value: i32,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(self)
+	}
+
+	fn visit_i64<'scale, 'resolver>(
+		self,
+		_value: i64,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(self)
+	}
+
+	fn visit_i128<'scale, 'resolver>(
+		self,
+		_value: i128,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(self)
+	}
+
+	fn visit_i256<'resolver>(
+		self,
+		_value: &[u8; 32],
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'_, 'resolver>, Self::Error> {
+		Ok(self)
+	}
+
+	fn visit_sequence<'scale, 'resolver>(
+		self,
+		value: &mut scale_decode::visitor::types::Sequence<'scale, 'resolver, Self::TypeResolver>,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		let remaining = value.remaining();
+		decode_items(value, remaining, self)
+	}
+
+	fn visit_composite<'scale, 'resolver>(
+		self,
+		value: &mut scale_decode::visitor::types::Composite<'scale, 'resolver, Self::TypeResolver>,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		let remaining = value.remaining();
+		decode_items(value, remaining, self)
+	}
+
+	fn visit_tuple<'scale, 'resolver>(
+		self,
+		value: &mut scale_decode::visitor::types::Tuple<'scale, 'resolver, Self::TypeResolver>,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		let remaining = value.remaining();
+		decode_items(value, remaining, self)
+	}
+
+	fn visit_str<'scale, 'resolver>(
+		self,
+		_value: &mut scale_decode::visitor::types::Str<'scale>,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(self)
+	}
+
+	fn visit_variant<'scale, 'resolver>(
+		mut self,
+		value: &mut scale_decode::visitor::types::Variant<'scale, 'resolver, Self::TypeResolver>,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		// The call is an enumeration of the pallets with the enumeration of the calls as field.
+		match self.naming {
+			Some(Naming::Pallet(index)) => {
+				self.calls[index].pallet = value.name().to_string();
+				self.naming = Some(Naming::Call(index));
+			},
+			Some(Naming::Call(index)) => {
+				self.calls[index].call = value.name().to_string();
+				self.naming = None;
+			},
+			None => {},
+		}
+
+		let remaining = value.fields().remaining();
+		decode_items(value.fields(), remaining, self)
+	}
+
+	fn visit_array<'scale, 'resolver>(
+		self,
+		value: &mut scale_decode::visitor::types::Array<'scale, 'resolver, Self::TypeResolver>,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		let remaining = value.remaining();
+		decode_items(value, remaining, self)
+	}
+
+	fn visit_bitsequence<'scale, 'resolver>(
+		self,
+		_value: &mut scale_decode::visitor::types::BitSequence<'scale>,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(self)
+	}
+}
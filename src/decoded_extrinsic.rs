@@ -0,0 +1,544 @@
+//! The values of a decoded extrinsic.
+
+use alloc::{collections::BTreeMap, format, string::String, vec::Vec};
+
+use crate::{
+	merkle_tree::Proof,
+	types::{Hash, MetadataDigest},
+	value::Value,
+};
+
+/// A decoded extrinsic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedExtrinsic {
+	/// The address of the signer, `Some(_)` if the extrinsic is signed.
+	pub address: Option<Value>,
+	/// The signature, `Some(_)` if the extrinsic is signed.
+	pub signature: Option<Value>,
+	/// The values of the signed extensions.
+	pub signed_extensions: SignedExtensionValues,
+	/// The call.
+	pub call: Value,
+	/// The encoded call, signed extensions and additional signed data.
+	pub(crate) signing_payload: Option<Vec<u8>>,
+}
+
+impl DecodedExtrinsic {
+	/// Returns the payload that was signed by the signer.
+	///
+	/// This is the encoded call, followed by the encoded values of the signed extensions that are
+	/// included in the extrinsic and the additional signed data. Returns `None` if the extrinsic
+	/// isn't signed or the additional signed data wasn't passed for decoding. Payloads longer than
+	/// 256 bytes are hashed with `blake2_256` before signing.
+	pub fn signing_payload(&self) -> Option<&[u8]> {
+		self.signing_payload.as_deref()
+	}
+}
+
+/// The values of a signed extension.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SignedExtensionValue {
+	/// The value that is included in the extrinsic, `Some(_)` if the extrinsic is signed.
+	pub included_in_extrinsic: Option<Value>,
+	/// The value that is included in the signed data, `Some(_)` if the additional signed data was
+	/// decoded.
+	pub included_in_signed_data: Option<Value>,
+}
+
+/// The values of the signed extensions, by their identifier.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SignedExtensionValues(pub(crate) BTreeMap<String, SignedExtensionValue>);
+
+impl SignedExtensionValues {
+	/// Returns the values of the signed extension with the given `identifier`.
+	pub fn get(&self, identifier: &str) -> Option<&SignedExtensionValue> {
+		self.0.get(identifier)
+	}
+
+	/// Insert the `value` of the signed extension with the given `identifier`.
+	///
+	/// Returns the previous value, if there was any.
+	pub fn insert(
+		&mut self,
+		identifier: impl Into<String>,
+		value: SignedExtensionValue,
+	) -> Option<SignedExtensionValue> {
+		self.0.insert(identifier.into(), value)
+	}
+
+	/// Returns an iterator over all signed extensions and their values.
+	pub fn iter(&self) -> impl Iterator<Item = (&str, &SignedExtensionValue)> {
+		self.0.iter().map(|(identifier, value)| (identifier.as_str(), value))
+	}
+
+	fn included_in_extrinsic(&self, identifier: &str) -> Option<&Value> {
+		self.get(identifier)?.included_in_extrinsic.as_ref()
+	}
+
+	fn included_in_signed_data(&self, identifier: &str) -> Option<&Value> {
+		self.get(identifier)?.included_in_signed_data.as_ref()
+	}
+
+	/// Returns the nonce of `CheckNonce`.
+	pub fn nonce(&self) -> Option<u128> {
+		self.included_in_extrinsic("CheckNonce")?.inner().as_unsigned()
+	}
+
+	/// Returns the tip of `ChargeTransactionPayment`.
+	pub fn tip(&self) -> Option<u128> {
+		self.included_in_extrinsic("ChargeTransactionPayment")?.inner().as_unsigned()
+	}
+
+	/// Returns the tip and the asset of `ChargeAssetTxPayment`.
+	pub fn asset_tip(&self) -> Option<AssetTip> {
+		let value = self.included_in_extrinsic("ChargeAssetTxPayment")?;
+
+		let asset_id = as_option(value.field("asset_id")?)?.cloned();
+
+		Some(AssetTip { tip: value.field("tip")?.inner().as_unsigned()?, asset_id })
+	}
+
+	/// Returns the era and the hash of the birth block of `CheckMortality`.
+	pub fn mortality(&self) -> Option<Mortality> {
+		let era = Era::from_value(self.included_in_extrinsic("CheckMortality")?.inner())?;
+
+		let birth_block_hash = match self.included_in_signed_data("CheckMortality") {
+			Some(hash) => Some(hash.inner().as_bytes()?.try_into().ok()?),
+			None => None,
+		};
+
+		Some(Mortality { era, birth_block_hash })
+	}
+
+	/// Returns the genesis hash of `CheckGenesis`.
+	pub fn genesis_hash(&self) -> Option<Hash> {
+		self.included_in_signed_data("CheckGenesis")?
+			.inner()
+			.as_bytes()?
+			.try_into()
+			.ok()
+	}
+
+	/// Returns the spec version of `CheckSpecVersion`.
+	pub fn spec_version(&self) -> Option<u32> {
+		self.included_in_signed_data("CheckSpecVersion")?
+			.inner()
+			.as_unsigned()?
+			.try_into()
+			.ok()
+	}
+
+	/// Returns the transaction version of `CheckTxVersion`.
+	pub fn transaction_version(&self) -> Option<u32> {
+		self.included_in_signed_data("CheckTxVersion")?
+			.inner()
+			.as_unsigned()?
+			.try_into()
+			.ok()
+	}
+
+	/// Returns if `CheckMetadataHash` is enabled.
+	pub fn metadata_hash_enabled(&self) -> Option<bool> {
+		match self.included_in_extrinsic("CheckMetadataHash")?.inner().variant_name()? {
+			"Enabled" => Some(true),
+			"Disabled" => Some(false),
+			_ => None,
+		}
+	}
+
+	/// Returns the metadata hash of `CheckMetadataHash`.
+	///
+	/// Returns `Some(None)` if the signed data doesn't contain a metadata hash.
+	pub fn metadata_hash(&self) -> Option<Option<Hash>> {
+		match as_option(self.included_in_signed_data("CheckMetadataHash")?)? {
+			Some(hash) => Some(Some(hash.inner().as_bytes()?.try_into().ok()?)),
+			None => Some(None),
+		}
+	}
+
+	/// Check the values of the well-known signed extensions against the `digest`.
+	///
+	/// Checks that the spec version of `CheckSpecVersion` is the one of the `digest` and that the
+	/// metadata hash of `CheckMetadataHash` is the hash of the `digest`. Signed extensions that
+	/// are not used by the runtime are not checked. Returns all the mismatches that were found.
+	pub fn check_digest(&self, digest: &MetadataDigest) -> Result<(), Vec<DigestMismatch>> {
+		let mut mismatches = Vec::new();
+
+		if let Some(values) = self.get("CheckSpecVersion") {
+			match (digest, self.spec_version()) {
+				(MetadataDigest::V1 { spec_version, .. }, Some(found))
+					if *spec_version != found =>
+					mismatches.push(DigestMismatch::SpecVersion { expected: *spec_version, found }),
+				(_, None) if values.included_in_signed_data.is_some() =>
+					mismatches.push(DigestMismatch::InvalidValue("CheckSpecVersion")),
+				(_, None) => mismatches.push(DigestMismatch::MissingSignedData("CheckSpecVersion")),
+				_ => {},
+			}
+		}
+
+		if let Some(values) = self.get("CheckMetadataHash") {
+			let expected = digest.hash();
+
+			match (self.metadata_hash_enabled(), self.metadata_hash()) {
+				// The hash is only part of the signed data, when the mode is enabled.
+				(Some(true), Some(found)) | (None, Some(found @ Some(_)))
+					if found != Some(expected) =>
+					mismatches.push(DigestMismatch::MetadataHash { expected, found }),
+				(Some(false), Some(Some(_))) =>
+					mismatches.push(DigestMismatch::InvalidValue("CheckMetadataHash")),
+				(_, None) if values.included_in_signed_data.is_none() =>
+					mismatches.push(DigestMismatch::MissingSignedData("CheckMetadataHash")),
+				(_, None) => mismatches.push(DigestMismatch::InvalidValue("CheckMetadataHash")),
+				_ => {},
+			}
+		}
+
+		if mismatches.is_empty() {
+			Ok(())
+		} else {
+			Err(mismatches)
+		}
+	}
+}
+
+/// Returns the value of an `Option<T>`.
+///
+/// Returns `None` if `value` isn't an `Option<T>`.
+fn as_option(value: &Value) -> Option<Option<&Value>> {
+	match value {
+		Value::Variant { name, fields, .. } if name == "Some" && fields.len() == 1 =>
+			Some(Some(&fields[0].1)),
+		Value::Variant { name, fields, .. } if name == "None" && fields.is_empty() => Some(None),
+		_ => None,
+	}
+}
+
+/// A mismatch between the values of the signed extensions and a [`MetadataDigest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DigestMismatch {
+	/// The spec version of `CheckSpecVersion` is different.
+	SpecVersion { expected: u32, found: u32 },
+	/// The metadata hash of `CheckMetadataHash` is not the hash of the digest.
+	///
+	/// `found` is `None` if the mode is enabled, but the signed data doesn't contain a hash.
+	MetadataHash { expected: Hash, found: Option<Hash> },
+	/// The value of the signed extension with the given identifier has an unexpected form.
+	InvalidValue(&'static str),
+	/// The signed data of the signed extension with the given identifier wasn't decoded.
+	MissingSignedData(&'static str),
+}
+
+/// The tip of `ChargeAssetTxPayment`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetTip {
+	/// The tip.
+	pub tip: u128,
+	/// The asset the fees are paid with, `None` for the native asset.
+	pub asset_id: Option<Value>,
+}
+
+/// The mortality of an extrinsic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Era {
+	/// The extrinsic is valid forever.
+	Immortal,
+	/// The extrinsic is only valid for a certain period, stored as the two encoded bytes.
+	Mortal(u8, u8),
+}
+
+impl Era {
+	/// Returns the era of `value`, the decoded value of an `sp_runtime::generic::Era`.
+	pub fn from_value(value: &Value) -> Option<Self> {
+		match value {
+			Value::Variant { name, fields, .. } if name == "Immortal" && fields.is_empty() =>
+				Some(Self::Immortal),
+			Value::Variant { index, fields, .. } if fields.len() == 1 =>
+				Some(Self::Mortal(*index, u8::try_from(fields[0].1.as_unsigned()?).ok()?)),
+			_ => None,
+		}
+	}
+
+	/// Decode the era from `encoded` using the leaves of `proof`.
+	///
+	/// The `proof` needs to contain the leaf of the `sp_runtime::generic::Era` variant that is
+	/// encoded. This doesn't require the rest of the extrinsic to be decoded.
+	pub fn decode_with_proof(encoded: &[u8], proof: &Proof) -> Result<Self, String> {
+		let index = *encoded.first().ok_or("Era is empty")?;
+
+		let variant = proof
+			.leaves
+			.iter()
+			.filter(|leaf| leaf.path.ends_with(&["generic".into(), "era".into(), "Era".into()]))
+			.filter_map(|leaf| leaf.type_def.as_enumeration())
+			.find(|variant| variant.index.0 == u32::from(index))
+			.ok_or_else(|| format!("Proof doesn't contain the era variant `{index}`"))?;
+
+		match (variant.fields.len(), encoded.len()) {
+			(0, 1) if variant.name == "Immortal" => Ok(Self::Immortal),
+			(1, 2) => Ok(Self::Mortal(index, encoded[1])),
+			_ => Err(format!("Invalid encoding for era variant `{}`", variant.name)),
+		}
+	}
+
+	/// Returns the validity of the era.
+	///
+	/// If `current_block` is `Some(_)`, the birth and the death block are calculated like the
+	/// runtime does when the extrinsic is included at `current_block`. Returns an error if the
+	/// period or the phase are invalid.
+	pub fn validity(&self, current_block: Option<u64>) -> Result<Validity, String> {
+		let Self::Mortal(first, second) = *self else { return Ok(Validity::Immortal) };
+
+		let encoded = u64::from(first) + (u64::from(second) << 8);
+		let period = 2 << (encoded % (1 << 4));
+		let quantize_factor = (period >> 12).max(1);
+		let phase = (encoded >> 4) * quantize_factor;
+
+		if period < 4 || phase >= period {
+			return Err(format!("Invalid era with period `{period}` and phase `{phase}`"));
+		}
+
+		let birth_block =
+			current_block.map(|current| (current.max(phase) - phase) / period * period + phase);
+
+		Ok(Validity::Mortal {
+			period,
+			phase,
+			birth_block,
+			death_block: birth_block.map(|birth| birth + period),
+		})
+	}
+}
+
+/// The validity of an [`Era`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Validity {
+	/// The extrinsic is valid forever.
+	Immortal,
+	/// The extrinsic is valid for `period` blocks.
+	Mortal {
+		/// The number of blocks the extrinsic is valid.
+		period: u64,
+		/// The phase of the birth block in the period.
+		phase: u64,
+		/// The first block the extrinsic is valid at, `Some(_)` if the current block is known.
+		birth_block: Option<u64>,
+		/// The first block the extrinsic isn't valid anymore, `Some(_)` if the current block is
+		/// known.
+		death_block: Option<u64>,
+	},
+}
+
+/// The values of `CheckMortality`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mortality {
+	/// The era.
+	pub era: Era,
+	/// The hash of the block the era starts at, `Some(_)` if the additional signed data was
+	/// decoded.
+	pub birth_block_hash: Option<Hash>,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use alloc::vec;
+
+	#[test]
+	fn asset_tip_works() {
+		let asset_tip = |asset_id| {
+			let value = Value::Composite(vec![
+				(Some("tip".into()), Value::Unsigned(10)),
+				(Some("asset_id".into()), asset_id),
+			]);
+
+			SignedExtensionValues(BTreeMap::from([(
+				"ChargeAssetTxPayment".into(),
+				SignedExtensionValue { included_in_extrinsic: Some(value), ..Default::default() },
+			)]))
+			.asset_tip()
+		};
+
+		assert_eq!(
+			Some(AssetTip { tip: 10, asset_id: None }),
+			asset_tip(Value::Variant { name: "None".into(), index: 0, fields: Vec::new() })
+		);
+		assert_eq!(
+			Some(AssetTip { tip: 10, asset_id: Some(Value::Unsigned(1984)) }),
+			asset_tip(Value::Variant {
+				name: "Some".into(),
+				index: 1,
+				fields: vec![(None, Value::Unsigned(1984))],
+			})
+		);
+		assert_eq!(None, asset_tip(Value::Unsigned(1984)));
+	}
+
+	fn digest(spec_version: u32) -> MetadataDigest {
+		MetadataDigest::V1 {
+			types_tree_root: [1; 32],
+			extrinsic_metadata_hash: [2; 32],
+			spec_version,
+			spec_name: "nice".into(),
+			base58_prefix: 1,
+			decimals: 1,
+			token_symbol: "lol".into(),
+		}
+	}
+
+	fn signed_extensions(
+		spec_version: Option<Value>,
+		mode: Option<&str>,
+		metadata_hash: Option<Value>,
+	) -> SignedExtensionValues {
+		SignedExtensionValues(BTreeMap::from([
+			(
+				"CheckSpecVersion".into(),
+				SignedExtensionValue {
+					included_in_extrinsic: Some(Value::Composite(Vec::new())),
+					included_in_signed_data: spec_version,
+				},
+			),
+			(
+				"CheckMetadataHash".into(),
+				SignedExtensionValue {
+					included_in_extrinsic: mode.map(|mode| {
+						Value::Composite(vec![(
+							Some("mode".into()),
+							Value::Variant {
+								name: mode.into(),
+								index: (mode == "Enabled") as u8,
+								fields: Vec::new(),
+							},
+						)])
+					}),
+					included_in_signed_data: metadata_hash,
+				},
+			),
+		]))
+	}
+
+	fn some_hash(hash: Hash) -> Value {
+		Value::Variant {
+			name: "Some".into(),
+			index: 1,
+			fields: vec![(
+				None,
+				Value::Sequence(hash.iter().map(|b| Value::Unsigned((*b).into())).collect()),
+			)],
+		}
+	}
+
+	fn none() -> Value {
+		Value::Variant { name: "None".into(), index: 0, fields: Vec::new() }
+	}
+
+	#[test]
+	fn check_digest_works() {
+		let digest = digest(10);
+		let hash = digest.hash();
+		let spec_version = || Some(Value::Unsigned(10));
+
+		let check = |spec_version, mode, metadata_hash| {
+			signed_extensions(spec_version, mode, metadata_hash).check_digest(&digest)
+		};
+
+		assert_eq!(Ok(()), check(spec_version(), Some("Enabled"), Some(some_hash(hash))));
+		assert_eq!(Ok(()), check(spec_version(), Some("Disabled"), Some(none())));
+		assert_eq!(Ok(()), check(spec_version(), None, Some(some_hash(hash))));
+		assert_eq!(Ok(()), check(spec_version(), None, Some(none())));
+		assert_eq!(Ok(()), SignedExtensionValues::default().check_digest(&digest));
+
+		assert_eq!(
+			Err(vec![
+				DigestMismatch::SpecVersion { expected: 10, found: 11 },
+				DigestMismatch::MetadataHash { expected: hash, found: Some([0; 32]) },
+			]),
+			check(Some(Value::Unsigned(11)), Some("Enabled"), Some(some_hash([0; 32])))
+		);
+		assert_eq!(
+			Err(vec![DigestMismatch::MetadataHash { expected: hash, found: None }]),
+			check(spec_version(), Some("Enabled"), Some(none()))
+		);
+		assert_eq!(
+			Err(vec![DigestMismatch::MetadataHash { expected: hash, found: Some([0; 32]) }]),
+			check(spec_version(), None, Some(some_hash([0; 32])))
+		);
+		assert_eq!(
+			Err(vec![DigestMismatch::InvalidValue("CheckMetadataHash")]),
+			check(spec_version(), Some("Disabled"), Some(some_hash(hash)))
+		);
+		assert_eq!(
+			Err(vec![
+				DigestMismatch::InvalidValue("CheckSpecVersion"),
+				DigestMismatch::InvalidValue("CheckMetadataHash"),
+			]),
+			check(Some(Value::Bool(true)), Some("Enabled"), Some(Value::Bool(true)))
+		);
+		assert_eq!(
+			Err(vec![
+				DigestMismatch::MissingSignedData("CheckSpecVersion"),
+				DigestMismatch::MissingSignedData("CheckMetadataHash"),
+			]),
+			check(None, Some("Enabled"), None)
+		);
+
+		// A disabled digest has no spec version, but the metadata hash still needs to match.
+		assert_eq!(
+			Ok(()),
+			signed_extensions(
+				spec_version(),
+				Some("Enabled"),
+				Some(some_hash(MetadataDigest::Disabled.hash()))
+			)
+			.check_digest(&MetadataDigest::Disabled)
+		);
+	}
+
+	#[test]
+	fn era_validity_works() {
+		assert_eq!(Ok(Validity::Immortal), Era::Immortal.validity(Some(100)));
+
+		// `Era::mortal(64, 42)` encodes as `0x02a5`.
+		let era = Era::Mortal(0xa5, 0x02);
+		assert_eq!(
+			Ok(Validity::Mortal { period: 64, phase: 42, birth_block: None, death_block: None }),
+			era.validity(None)
+		);
+		assert_eq!(
+			Ok(Validity::Mortal {
+				period: 64,
+				phase: 42,
+				birth_block: Some(938),
+				death_block: Some(1002),
+			}),
+			era.validity(Some(1000))
+		);
+		// The birth block is never before the phase.
+		assert_eq!(
+			Ok(Validity::Mortal {
+				period: 64,
+				phase: 42,
+				birth_block: Some(42),
+				death_block: Some(106),
+			}),
+			era.validity(Some(10))
+		);
+
+		// Periods above `4096` are quantized.
+		let era = Era::Mortal(0x0f, 0xff);
+		assert_eq!(
+			Ok(Validity::Mortal {
+				period: 65536,
+				phase: 4080 * 16,
+				birth_block: Some(4080 * 16),
+				death_block: Some(4080 * 16 + 65536),
+			}),
+			era.validity(Some(70000))
+		);
+
+		// Period of `2`.
+		assert!(Era::Mortal(0x00, 0x00).validity(None).is_err());
+		// Phase bigger than the period.
+		assert!(Era::Mortal(0x41, 0x00).validity(None).is_err());
+	}
+}
@@ -0,0 +1,437 @@
+//! Differences between two versions of the metadata.
+//!
+//! Types are matched by their path and the variants of enumerations by their name, as the type ids
+//! change between runtime versions.
+
+use alloc::{
+	collections::{BTreeMap, BTreeSet},
+	format,
+	string::String,
+	vec::Vec,
+};
+
+use crate::{
+	from_frame_metadata::TypeInformation,
+	merkle_tree::MerkleTree,
+	types::{Field, Type, TypeDef, TypeRef},
+};
+
+/// Identifies a variant of an enumeration.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct VariantPath {
+	/// The path of the enumeration.
+	pub path: Vec<String>,
+	/// The name of the variant.
+	pub variant: String,
+}
+
+/// The differences between two [`ExtrinsicMetadata`](crate::types::ExtrinsicMetadata).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtrinsicMetadataDiff {
+	/// The extrinsic version changed.
+	pub version_changed: bool,
+	/// The address type was replaced by another type.
+	pub address_ty_changed: bool,
+	/// The call type was replaced by another type.
+	pub call_ty_changed: bool,
+	/// The signature type was replaced by another type.
+	pub signature_ty_changed: bool,
+	/// The identifiers of the signed extensions that were added.
+	pub added_signed_extensions: Vec<String>,
+	/// The identifiers of the signed extensions that were removed.
+	pub removed_signed_extensions: Vec<String>,
+	/// The identifiers of the signed extensions that have changed types.
+	pub modified_signed_extensions: Vec<String>,
+	/// The order of the signed extensions that exist in both changed.
+	pub signed_extensions_reordered: bool,
+}
+
+impl ExtrinsicMetadataDiff {
+	/// Returns `true` if nothing changed.
+	pub fn is_empty(&self) -> bool {
+		*self == Self::default()
+	}
+}
+
+/// The differences between two [`TypeInformation`].
+///
+/// Types are matched by their path and variants of enumerations by their name, as the type ids are
+/// not stable between two runtime versions. Types without a path (tuples, sequences, arrays, etc)
+/// are not reported on their own. Changes to them are reported as changes of the types that are
+/// referencing them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetadataDiff {
+	/// The paths of the types that were added.
+	pub added_types: BTreeSet<Vec<String>>,
+	/// The paths of the types that were removed.
+	pub removed_types: BTreeSet<Vec<String>>,
+	/// The paths of the types that were modified.
+	///
+	/// For enumerations only a change of the kind of the type is reported here, changes of
+	/// variants are reported individually.
+	pub modified_types: BTreeSet<Vec<String>>,
+	/// The variants that were added to enumerations that exist in both.
+	pub added_variants: BTreeSet<VariantPath>,
+	/// The variants that were removed from enumerations that exist in both.
+	pub removed_variants: BTreeSet<VariantPath>,
+	/// The variants that were modified, e.g. their index or their fields changed.
+	pub modified_variants: BTreeSet<VariantPath>,
+	/// The differences of the [`ExtrinsicMetadata`](crate::types::ExtrinsicMetadata).
+	pub extrinsic_metadata: ExtrinsicMetadataDiff,
+	/// The root of the types tree changed and thus, all existing proofs are invalid.
+	pub types_root_changed: bool,
+}
+
+impl MetadataDiff {
+	/// Returns `true` if there are no changes to any type or the extrinsic metadata.
+	///
+	/// The types root may still change, e.g. because types were renumbered.
+	pub fn is_empty(&self) -> bool {
+		self.added_types.is_empty() &&
+			self.removed_types.is_empty() &&
+			self.modified_types.is_empty() &&
+			self.added_variants.is_empty() &&
+			self.removed_variants.is_empty() &&
+			self.modified_variants.is_empty() &&
+			self.extrinsic_metadata.is_empty()
+	}
+}
+
+/// Calculate the differences between `old` and `new`.
+pub fn diff_type_information(old: &TypeInformation, new: &TypeInformation) -> MetadataDiff {
+	let old_shapes = type_shapes(old);
+	let new_shapes = type_shapes(new);
+
+	let mut diff = MetadataDiff {
+		extrinsic_metadata: diff_extrinsic_metadata(old, new),
+		types_root_changed: MerkleTree::new(old.types.clone()).root() !=
+			MerkleTree::new(new.types.clone()).root(),
+		..Default::default()
+	};
+
+	for (path, old_variants) in &old_shapes {
+		let Some(new_variants) = new_shapes.get(path) else {
+			diff.removed_types.insert(path.clone());
+			continue;
+		};
+
+		// The kind of the type changed or it isn't an enumeration.
+		if old_variants.get(&None) != new_variants.get(&None) {
+			diff.modified_types.insert(path.clone());
+		}
+
+		for (variant, old_shape) in old_variants {
+			let Some(variant) = variant else { continue };
+			let variant_path = VariantPath { path: path.clone(), variant: variant.clone() };
+
+			match new_variants.get(&Some(variant.clone())) {
+				None => {
+					diff.removed_variants.insert(variant_path);
+				},
+				Some(new_shape) if new_shape != old_shape => {
+					diff.modified_variants.insert(variant_path);
+				},
+				Some(_) => {},
+			}
+		}
+
+		for variant in new_variants.keys().flatten() {
+			if !old_variants.contains_key(&Some(variant.clone())) {
+				diff.added_variants
+					.insert(VariantPath { path: path.clone(), variant: variant.clone() });
+			}
+		}
+	}
+
+	diff.added_types =
+		new_shapes.keys().filter(|p| !old_shapes.contains_key(*p)).cloned().collect();
+
+	diff
+}
+
+/// The shapes of all types with a path.
+///
+/// The types are grouped by path and by variant name (`None` for types that aren't enumerations).
+/// As multiple types can share the same path (e.g. `Option<u32>` and `Option<bool>`), there is a
+/// set of shapes per entry.
+type TypeShapes = BTreeMap<Vec<String>, BTreeMap<Option<String>, BTreeSet<String>>>;
+
+fn type_shapes(type_information: &TypeInformation) -> TypeShapes {
+	let mut shapes = TypeShapes::new();
+
+	for ty in type_information.types.values().filter(|ty| !ty.path.is_empty()) {
+		let (variant, shape) = match &ty.type_def {
+			TypeDef::Enumeration(v) => (
+				Some(v.name.clone()),
+				format!("{}: {}", v.index.0, render_fields(&v.fields, type_information)),
+			),
+			_ => (None, render_type_def(&ty.type_def, type_information)),
+		};
+
+		shapes
+			.entry(ty.path.clone())
+			.or_default()
+			.entry(variant)
+			.or_default()
+			.insert(shape);
+	}
+
+	shapes
+}
+
+fn diff_extrinsic_metadata(old: &TypeInformation, new: &TypeInformation) -> ExtrinsicMetadataDiff {
+	let old_metadata = &old.extrinsic_metadata;
+	let new_metadata = &new.extrinsic_metadata;
+
+	let signed_extensions = |type_information: &TypeInformation| {
+		type_information
+			.extrinsic_metadata
+			.signed_extensions
+			.iter()
+			.map(|se| {
+				(
+					se.identifier.clone(),
+					(
+						render_type_ref(&se.included_in_extrinsic, type_information),
+						render_type_ref(&se.included_in_signed_data, type_information),
+					),
+				)
+			})
+			.collect::<Vec<_>>()
+	};
+
+	let old_signed_extensions = signed_extensions(old);
+	let new_signed_extensions = signed_extensions(new);
+	let find = |signed_extensions: &[(String, _)], identifier: &str| {
+		signed_extensions.iter().position(|(i, _)| i == identifier)
+	};
+
+	let mut diff = ExtrinsicMetadataDiff {
+		version_changed: old_metadata.version != new_metadata.version,
+		address_ty_changed: render_type_ref(&old_metadata.address_ty, old) !=
+			render_type_ref(&new_metadata.address_ty, new),
+		call_ty_changed: render_type_ref(&old_metadata.call_ty, old) !=
+			render_type_ref(&new_metadata.call_ty, new),
+		signature_ty_changed: render_type_ref(&old_metadata.signature_ty, old) !=
+			render_type_ref(&new_metadata.signature_ty, new),
+		..Default::default()
+	};
+
+	let mut common = Vec::new();
+	for (identifier, old_types) in &old_signed_extensions {
+		match find(&new_signed_extensions, identifier) {
+			None => diff.removed_signed_extensions.push(identifier.clone()),
+			Some(pos) => {
+				common.push(identifier);
+
+				if new_signed_extensions[pos].1 != *old_types {
+					diff.modified_signed_extensions.push(identifier.clone());
+				}
+			},
+		}
+	}
+
+	diff.added_signed_extensions = new_signed_extensions
+		.iter()
+		.filter(|(i, _)| find(&old_signed_extensions, i).is_none())
+		.map(|(i, _)| i.clone())
+		.collect();
+
+	diff.signed_extensions_reordered = !common
+		.iter()
+		.copied()
+		.eq(new_signed_extensions.iter().map(|(i, _)| i).filter(|i| common.contains(i)));
+
+	diff
+}
+
+/// Render the given `type_ref` as a human readable string.
+///
+/// Types with a path are rendered using their path, all other types are rendered using their
+/// structure.
+fn render_type_ref(type_ref: &TypeRef, type_information: &TypeInformation) -> String {
+	let id = match type_ref {
+		TypeRef::Bool => return "bool".into(),
+		TypeRef::Char => return "char".into(),
+		TypeRef::Str => return "str".into(),
+		TypeRef::U8 => return "u8".into(),
+		TypeRef::U16 => return "u16".into(),
+		TypeRef::U32 => return "u32".into(),
+		TypeRef::U64 => return "u64".into(),
+		TypeRef::U128 => return "u128".into(),
+		TypeRef::U256 => return "u256".into(),
+		TypeRef::I8 => return "i8".into(),
+		TypeRef::I16 => return "i16".into(),
+		TypeRef::I32 => return "i32".into(),
+		TypeRef::I64 => return "i64".into(),
+		TypeRef::I128 => return "i128".into(),
+		TypeRef::I256 => return "i256".into(),
+		TypeRef::CompactU8 => return "Compact<u8>".into(),
+		TypeRef::CompactU16 => return "Compact<u16>".into(),
+		TypeRef::CompactU32 => return "Compact<u32>".into(),
+		TypeRef::CompactU64 => return "Compact<u64>".into(),
+		TypeRef::CompactU128 => return "Compact<u128>".into(),
+		TypeRef::CompactU256 => return "Compact<u256>".into(),
+		TypeRef::Void => return "()".into(),
+		TypeRef::ById(id) => id.0,
+	};
+
+	let types = type_information.types_by_id(id).map(|(_, ty)| ty).collect::<Vec<&Type>>();
+
+	match types.first() {
+		None => format!("<unknown {id}>"),
+		Some(ty) if !ty.path.is_empty() => ty.path.join("::"),
+		Some(ty) if ty.type_def.as_enumeration().is_some() => {
+			let variants = types
+				.iter()
+				.filter_map(|ty| ty.type_def.as_enumeration())
+				.map(|v| {
+					format!(
+						"{}({}) {}",
+						v.name,
+						v.index.0,
+						render_fields(&v.fields, type_information)
+					)
+				})
+				.collect::<Vec<_>>();
+
+			format!("enum {{ {} }}", variants.join(", "))
+		},
+		Some(ty) => render_type_def(&ty.type_def, type_information),
+	}
+}
+
+fn render_type_def(type_def: &TypeDef, type_information: &TypeInformation) -> String {
+	match type_def {
+		TypeDef::Composite(fields) => render_fields(fields, type_information),
+		TypeDef::Enumeration(v) =>
+			format!("{}({}) {}", v.name, v.index.0, render_fields(&v.fields, type_information)),
+		TypeDef::Sequence(s) => format!("Vec<{}>", render_type_ref(s, type_information)),
+		TypeDef::Array(a) =>
+			format!("[{}; {}]", render_type_ref(&a.type_param, type_information), a.len),
+		TypeDef::Tuple(t) => format!(
+			"({})",
+			t.iter()
+				.map(|t| render_type_ref(t, type_information))
+				.collect::<Vec<_>>()
+				.join(", ")
+		),
+		TypeDef::BitSequence(b) =>
+			format!("BitSequence<{}, {}>", b.num_bytes, b.least_significant_bit_first),
+	}
+}
+
+fn render_fields(fields: &[Field], type_information: &TypeInformation) -> String {
+	let fields = fields
+		.iter()
+		.map(|f| {
+			let ty = render_type_ref(&f.ty, type_information);
+
+			match &f.name {
+				Some(name) => format!("{name}: {ty}"),
+				None => ty,
+			}
+		})
+		.collect::<Vec<_>>();
+
+	format!("{{ {} }}", fields.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{generate_type_information, merkle_tree::TypeId, tests::load_metadata};
+
+	#[test]
+	fn diff_with_itself_is_empty() {
+		let type_information =
+			generate_type_information(&load_metadata("rococo_metadata_v15")).unwrap();
+
+		let diff = diff_type_information(&type_information, &type_information);
+		assert!(diff.is_empty());
+		assert!(!diff.types_root_changed);
+	}
+
+	#[test]
+	fn diff_between_different_chains_works() {
+		let polkadot = generate_type_information(&load_metadata("polkadot_metadata_v15")).unwrap();
+		let kusama = generate_type_information(&load_metadata("kusama_metadata_v15")).unwrap();
+
+		let diff = diff_type_information(&polkadot, &kusama);
+		assert!(!diff.is_empty());
+		assert!(diff.types_root_changed);
+		// Kusama has a `Society` pallet, Polkadot not.
+		assert!(diff.added_types.contains(&vec![
+			"pallet_society".to_string(),
+			"pallet".into(),
+			"Call".into()
+		]));
+	}
+
+	#[test]
+	fn diff_detects_changes() {
+		let old = generate_type_information(&load_metadata("rococo_metadata_v15")).unwrap();
+		let mut new = old.clone();
+
+		let call_ty = old.extrinsic_metadata.call_ty.id().unwrap();
+		let call_path = old.types_by_id(call_ty).next().unwrap().1.path.clone();
+
+		// Remove the `System` pallet from the calls.
+		let system = *new
+			.types_by_id(call_ty)
+			.find(|(_, ty)| ty.type_def.as_enumeration().unwrap().name == "System")
+			.unwrap()
+			.0;
+		new.types.remove(&system);
+
+		// Rename the first variant of the calls.
+		let (_, first) = new
+			.types
+			.range_mut(TypeId::Enumeration { type_id: call_ty, variant: 0 }..)
+			.next()
+			.unwrap();
+		let TypeDef::Enumeration(first) = &mut first.type_def else { panic!("Is an enum") };
+		let first_name = core::mem::replace(&mut first.name, "Renamed".into());
+
+		// Change the type of the `nonce` in `CheckNonce`.
+		let check_nonce = new
+			.extrinsic_metadata
+			.signed_extensions
+			.iter_mut()
+			.find(|se| se.identifier == "CheckNonce")
+			.unwrap();
+		check_nonce.included_in_extrinsic = TypeRef::CompactU64;
+
+		// Remove `CheckGenesis`.
+		new.extrinsic_metadata
+			.signed_extensions
+			.retain(|se| se.identifier != "CheckGenesis");
+
+		let diff = diff_type_information(&old, &new);
+
+		assert!(diff.types_root_changed);
+		assert!(diff.added_types.is_empty());
+		assert!(diff.removed_types.is_empty());
+		assert!(diff.modified_types.is_empty());
+		assert_eq!(
+			BTreeSet::from([VariantPath { path: call_path.clone(), variant: "Renamed".into() }]),
+			diff.added_variants
+		);
+		assert_eq!(
+			BTreeSet::from([
+				VariantPath { path: call_path.clone(), variant: "System".into() },
+				VariantPath { path: call_path, variant: first_name }
+			]),
+			diff.removed_variants
+		);
+		assert!(diff.modified_variants.is_empty());
+		assert_eq!(
+			ExtrinsicMetadataDiff {
+				removed_signed_extensions: vec!["CheckGenesis".into()],
+				modified_signed_extensions: vec!["CheckNonce".into()],
+				..Default::default()
+			},
+			diff.extrinsic_metadata
+		);
+	}
+}
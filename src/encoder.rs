@@ -0,0 +1,949 @@
+//! Encoding of values using the [`Type`]s of the merkle tree.
+//!
+//! The counterpart of the decoding in [`decode_extrinsic`](crate::decode_extrinsic), the encoded
+//! values decode to the same [`Value`]s again.
+
+use alloc::{collections::BTreeMap, format, string::String, vec::Vec};
+use codec::{Compact, Encode};
+
+use crate::{
+	decoded_extrinsic::SignedExtensionValues,
+	types::{ExtrinsicMetadata, Field, Type, TypeDef, TypeDefBitSequence, TypeRef},
+	value::{Fields, Value},
+};
+
+/// The signature of a signed extrinsic.
+pub struct ExtrinsicSignature<'a> {
+	/// The address of the signer, a value of the `address_ty`.
+	pub address: &'a Value,
+	/// The signature, a value of the `signature_ty`.
+	pub signature: &'a Value,
+	/// The encoded values of the signed extensions that are included in the extrinsic.
+	///
+	/// See [`encode_signed_extensions`].
+	pub extra: &'a [u8],
+}
+
+/// Encode the `value` of the type `type_ref`.
+///
+/// `types` need to contain all types of `value`, e.g. [`TypeInformation::types`] or the leaves of
+/// a proof. Variants of enumerations are selected by name and named fields are matched by name,
+/// unnamed fields by position. A value can be passed directly for a composite with exactly one
+/// field, e.g. the bytes of an `AccountId32`.
+///
+/// [`TypeInformation::types`]: crate::TypeInformation::types
+pub fn encode_value<'a>(
+	value: &Value,
+	type_ref: TypeRef,
+	types: impl IntoIterator<Item = &'a Type>,
+) -> Result<Vec<u8>, String> {
+	let mut out = Vec::new();
+	Encoder::new(types).encode(value, type_ref, &mut out)?;
+	Ok(out)
+}
+
+/// Encode the `call` of `pallet` with the given `args`.
+///
+/// `call_ty` is the [`call_ty`](ExtrinsicMetadata::call_ty) of the extrinsic metadata. See
+/// [`encode_value`] for how the `args` are encoded.
+pub fn encode_call<'a>(
+	pallet: &str,
+	call: &str,
+	args: Fields,
+	call_ty: TypeRef,
+	types: impl IntoIterator<Item = &'a Type>,
+) -> Result<Vec<u8>, String> {
+	let call = Value::Variant { name: call.into(), index: 0, fields: args };
+	let call = Value::Variant { name: pallet.into(), index: 0, fields: [(None, call)].into() };
+
+	encode_value(&call, call_ty, types)
+}
+
+/// Encode the values of the signed extensions.
+///
+/// Returns the encoded values that are included in the extrinsic and the encoded values that are
+/// included in the signed data. The values of signed extensions with an empty type can be
+/// omitted.
+pub fn encode_signed_extensions<'a>(
+	values: &SignedExtensionValues,
+	extrinsic_metadata: &ExtrinsicMetadata,
+	types: impl IntoIterator<Item = &'a Type>,
+) -> Result<(Vec<u8>, Vec<u8>), String> {
+	let encoder = Encoder::new(types);
+	let mut included_in_extrinsic = Vec::new();
+	let mut included_in_signed_data = Vec::new();
+
+	for se in &extrinsic_metadata.signed_extensions {
+		let value = values.get(&se.identifier);
+
+		for (value, type_ref, out) in [
+			(
+				value.and_then(|v| v.included_in_extrinsic.as_ref()),
+				se.included_in_extrinsic,
+				&mut included_in_extrinsic,
+			),
+			(
+				value.and_then(|v| v.included_in_signed_data.as_ref()),
+				se.included_in_signed_data,
+				&mut included_in_signed_data,
+			),
+		] {
+			match value {
+				Some(value) => encoder.encode(value, type_ref, out),
+				None => encoder.encode(&Value::Composite(Vec::new()), type_ref, out),
+			}
+			.map_err(|e| format!("Failed to encode signed extension ({}): {e}", se.identifier))?;
+		}
+	}
+
+	Ok((included_in_extrinsic, included_in_signed_data))
+}
+
+/// Assemble a version 4 extrinsic from the encoded `call`.
+///
+/// The extrinsic is signed if `signature` is `Some(_)`. Returns the extrinsic including its
+/// length prefix.
+pub fn encode_extrinsic<'a>(
+	call: &[u8],
+	signature: Option<ExtrinsicSignature>,
+	extrinsic_metadata: &ExtrinsicMetadata,
+	types: impl IntoIterator<Item = &'a Type>,
+) -> Result<Vec<u8>, String> {
+	let mut extrinsic = Vec::new();
+
+	match signature {
+		Some(signature) => {
+			let encoder = Encoder::new(types);
+
+			extrinsic.push(extrinsic_metadata.version | 0b1000_0000);
+			encoder
+				.encode(signature.address, extrinsic_metadata.address_ty, &mut extrinsic)
+				.map_err(|e| format!("Failed to encode address: {e}"))?;
+			encoder
+				.encode(signature.signature, extrinsic_metadata.signature_ty, &mut extrinsic)
+				.map_err(|e| format!("Failed to encode signature: {e}"))?;
+			extrinsic.extend_from_slice(signature.extra);
+		},
+		None => extrinsic.push(extrinsic_metadata.version),
+	}
+
+	extrinsic.extend_from_slice(call);
+
+	Ok(extrinsic.encode())
+}
+
+/// Convert the JSON `json` to a [`Value`] of the type `type_ref`.
+///
+/// The conversion follows the type, `types` are the same as for [`encode_value`]:
+///
+/// - Integers are numbers or decimal strings, for integers that don't fit into a JSON number like
+///   the 256bit integers.
+/// - Sequences and arrays of bytes can be `0x` prefixed hex strings.
+/// - Enumeration variants are objects with the name of the variant as only key and its fields as
+///   value. Variants without fields can also be given by their name only.
+/// - Fields are objects for named fields and arrays for unnamed fields. A single field can be given
+///   directly, e.g. the hex string of an `AccountId32`.
+///
+/// A call is converted using the [`call_ty`](ExtrinsicMetadata::call_ty), e.g.
+/// `{"Balances": {"transfer_keep_alive": {"dest": {"Id": "0x…"}, "value": 10}}}`.
+#[cfg(feature = "json")]
+pub fn value_from_json<'a>(
+	json: &serde_json::Value,
+	type_ref: TypeRef,
+	types: impl IntoIterator<Item = &'a Type>,
+) -> Result<Value, String> {
+	Encoder::new(types).convert_json(json, type_ref)
+}
+
+struct Encoder<'a> {
+	types: BTreeMap<u32, Vec<&'a Type>>,
+}
+
+impl<'a> Encoder<'a> {
+	fn new(types: impl IntoIterator<Item = &'a Type>) -> Self {
+		Self {
+			types: types.into_iter().fold(Default::default(), |mut map, ty| {
+				map.entry(ty.type_id.0).or_default().push(ty);
+				map
+			}),
+		}
+	}
+
+	fn encode(&self, value: &Value, type_ref: TypeRef, out: &mut Vec<u8>) -> Result<(), String> {
+		match type_ref {
+			TypeRef::Bool => match value {
+				Value::Bool(v) => v.encode_to(out),
+				v => return Err(format!("Expected `bool`, got {v:?}")),
+			},
+			TypeRef::Char => match value {
+				Value::Char(v) => u32::from(*v).encode_to(out),
+				v => return Err(format!("Expected `char`, got {v:?}")),
+			},
+			TypeRef::Str => match value {
+				Value::Str(v) => v.encode_to(out),
+				v => return Err(format!("Expected `str`, got {v:?}")),
+			},
+			TypeRef::U8 => u8::try_from(unsigned(value)?).map_err(out_of_range)?.encode_to(out),
+			TypeRef::U16 => u16::try_from(unsigned(value)?).map_err(out_of_range)?.encode_to(out),
+			TypeRef::U32 => u32::try_from(unsigned(value)?).map_err(out_of_range)?.encode_to(out),
+			TypeRef::U64 => u64::try_from(unsigned(value)?).map_err(out_of_range)?.encode_to(out),
+			TypeRef::U128 => unsigned(value)?.encode_to(out),
+			TypeRef::I8 => i8::try_from(signed(value)?).map_err(out_of_range)?.encode_to(out),
+			TypeRef::I16 => i16::try_from(signed(value)?).map_err(out_of_range)?.encode_to(out),
+			TypeRef::I32 => i32::try_from(signed(value)?).map_err(out_of_range)?.encode_to(out),
+			TypeRef::I64 => i64::try_from(signed(value)?).map_err(out_of_range)?.encode_to(out),
+			TypeRef::I128 => signed(value)?.encode_to(out),
+			TypeRef::U256 => out.extend(u256(value)?),
+			TypeRef::I256 => match value {
+				Value::I256(v) => out.extend(v),
+				Value::Signed(v) => {
+					let fill = if v.is_negative() { 0xff } else { 0 };
+					out.extend(v.to_le_bytes());
+					out.extend([fill; 16]);
+				},
+				v => return Err(format!("Expected a signed integer, got {v:?}")),
+			},
+			TypeRef::CompactU8 =>
+				Compact(u8::try_from(unsigned(value)?).map_err(out_of_range)?).encode_to(out),
+			TypeRef::CompactU16 =>
+				Compact(u16::try_from(unsigned(value)?).map_err(out_of_range)?).encode_to(out),
+			TypeRef::CompactU32 =>
+				Compact(u32::try_from(unsigned(value)?).map_err(out_of_range)?).encode_to(out),
+			TypeRef::CompactU64 =>
+				Compact(u64::try_from(unsigned(value)?).map_err(out_of_range)?).encode_to(out),
+			TypeRef::CompactU128 => Compact(unsigned(value)?).encode_to(out),
+			TypeRef::CompactU256 => encode_compact_u256(&u256(value)?, out),
+			TypeRef::Void => match value {
+				Value::Composite(fields) if fields.is_empty() => {},
+				Value::Sequence(values) if values.is_empty() => {},
+				v => return Err(format!("Expected an empty value, got {v:?}")),
+			},
+			TypeRef::ById(id) => self.encode_by_id(value, id.0, out)?,
+		}
+
+		Ok(())
+	}
+
+	fn encode_by_id(&self, value: &Value, type_id: u32, out: &mut Vec<u8>) -> Result<(), String> {
+		let types = self.types.get(&type_id).ok_or_else(|| format!("Unknown type id {type_id}"))?;
+		let first = types.first().ok_or_else(|| format!("{type_id} type is empty"))?;
+
+		match (&first.type_def, value) {
+			(TypeDef::Enumeration(_), Value::Variant { name, fields, .. }) => {
+				let variant = types
+					.iter()
+					.filter_map(|ty| ty.type_def.as_enumeration())
+					.find(|v| v.name == *name)
+					.ok_or_else(|| format!("Unknown variant `{name}` of type {type_id}"))?;
+
+				out.push(variant.index.0 as u8);
+				self.encode_fields(fields, &variant.fields, out)
+			},
+			(TypeDef::Composite(type_fields), Value::Composite(fields)) =>
+				self.encode_fields(fields, type_fields, out),
+			// A value for the only field of the composite.
+			(TypeDef::Composite(type_fields), value) if type_fields.len() == 1 =>
+				self.encode(value, type_fields[0].ty, out),
+			(TypeDef::Sequence(type_ref), Value::Sequence(values)) => {
+				Compact(values.len() as u32).encode_to(out);
+				values.iter().try_for_each(|v| self.encode(v, *type_ref, out))
+			},
+			(TypeDef::Array(array), Value::Sequence(values)) => {
+				if values.len() != array.len as usize {
+					return Err(format!(
+						"Expected {} items for type {type_id}, got {}",
+						array.len,
+						values.len()
+					))
+				}
+
+				values.iter().try_for_each(|v| self.encode(v, array.type_param, out))
+			},
+			(TypeDef::Tuple(type_refs), Value::Sequence(values)) => {
+				if values.len() != type_refs.len() {
+					return Err(format!(
+						"Expected {} items for type {type_id}, got {}",
+						type_refs.len(),
+						values.len()
+					))
+				}
+
+				values.iter().zip(type_refs).try_for_each(|(v, t)| self.encode(v, *t, out))
+			},
+			(TypeDef::BitSequence(bit_sequence), Value::BitSequence(bits)) =>
+				encode_bit_sequence(bits, bit_sequence, out),
+			(_, v) => Err(format!("Value {v:?} doesn't match type {type_id}")),
+		}
+	}
+
+	fn encode_fields(
+		&self,
+		values: &Fields,
+		fields: &[Field],
+		out: &mut Vec<u8>,
+	) -> Result<(), String> {
+		let named = !fields.is_empty() &&
+			fields.iter().all(|f| f.name.is_some()) &&
+			values.iter().all(|(n, _)| n.is_some());
+
+		if named {
+			if let Some((name, _)) = values
+				.iter()
+				.find(|(n, _)| !fields.iter().any(|f| f.name.as_ref() == n.as_ref()))
+			{
+				return Err(format!("Unknown field `{}`", name.as_deref().unwrap_or_default()))
+			}
+
+			fields.iter().try_for_each(|field| {
+				let name = field.name.as_deref().expect("Checked that all fields are named; qed");
+				let value = values
+					.iter()
+					.find_map(|(n, v)| (n.as_deref() == Some(name)).then_some(v))
+					.ok_or_else(|| format!("Missing field `{name}`"))?;
+
+				self.encode(value, field.ty, out)
+					.map_err(|e| format!("Failed to encode field `{name}`: {e}"))
+			})
+		} else {
+			if values.len() != fields.len() {
+				return Err(format!("Expected {} fields, got {}", fields.len(), values.len()))
+			}
+
+			values.iter().zip(fields).try_for_each(|((_, v), f)| self.encode(v, f.ty, out))
+		}
+	}
+}
+
+#[cfg(feature = "json")]
+impl Encoder<'_> {
+	fn convert_json(&self, json: &serde_json::Value, type_ref: TypeRef) -> Result<Value, String> {
+		use serde_json::Value as Json;
+
+		let value = match (type_ref, json) {
+			(TypeRef::Bool, Json::Bool(v)) => Value::Bool(*v),
+			(TypeRef::Char, Json::String(v)) if v.chars().count() == 1 =>
+				Value::Char(v.chars().next().expect("There is exactly one char; qed")),
+			(TypeRef::Str, Json::String(v)) => Value::Str(v.clone()),
+			(
+				TypeRef::U8 |
+				TypeRef::U16 |
+				TypeRef::U32 |
+				TypeRef::U64 |
+				TypeRef::U128 |
+				TypeRef::CompactU8 |
+				TypeRef::CompactU16 |
+				TypeRef::CompactU32 |
+				TypeRef::CompactU64 |
+				TypeRef::CompactU128,
+				Json::Number(_) | Json::String(_),
+			) => Value::Unsigned(json_integer(json)?),
+			(TypeRef::U256 | TypeRef::CompactU256, Json::Number(_) | Json::String(_)) =>
+				Value::U256(json_integer_256(json, false)?),
+			(
+				TypeRef::I8 | TypeRef::I16 | TypeRef::I32 | TypeRef::I64 | TypeRef::I128,
+				Json::Number(_) | Json::String(_),
+			) => Value::Signed(json_integer(json)?),
+			(TypeRef::I256, Json::Number(_) | Json::String(_)) =>
+				Value::I256(json_integer_256(json, true)?),
+			(TypeRef::Void, _) => {
+				self.convert_json_fields(json, &[])?;
+				Value::Composite(Vec::new())
+			},
+			(TypeRef::ById(id), _) => self.convert_json_by_id(json, id.0)?,
+			(type_ref, json) => return Err(format!("Expected {type_ref:?}, got {json}")),
+		};
+
+		Ok(value)
+	}
+
+	fn convert_json_by_id(&self, json: &serde_json::Value, type_id: u32) -> Result<Value, String> {
+		use serde_json::Value as Json;
+
+		let types = self.types.get(&type_id).ok_or_else(|| format!("Unknown type id {type_id}"))?;
+		let first = types.first().ok_or_else(|| format!("{type_id} type is empty"))?;
+
+		let value = match (&first.type_def, json) {
+			(TypeDef::Enumeration(_), Json::String(name)) =>
+				self.convert_json_variant(types, name, &Json::Null)?,
+			(TypeDef::Enumeration(_), Json::Object(map)) if map.len() == 1 => {
+				let (name, fields) = map.iter().next().expect("There is exactly one entry; qed");
+				self.convert_json_variant(types, name, fields)?
+			},
+			(TypeDef::Composite(fields), json) =>
+				Value::Composite(self.convert_json_fields(json, fields)?),
+			(TypeDef::Sequence(type_ref), json) =>
+				Value::Sequence(self.convert_json_items(json, *type_ref)?),
+			(TypeDef::Array(array), json) =>
+				Value::Sequence(self.convert_json_items(json, array.type_param)?),
+			(TypeDef::Tuple(type_refs), Json::Array(items)) if items.len() == type_refs.len() =>
+				Value::Sequence(
+					items
+						.iter()
+						.zip(type_refs)
+						.map(|(item, type_ref)| self.convert_json(item, *type_ref))
+						.collect::<Result<_, _>>()?,
+				),
+			(TypeDef::BitSequence(_), Json::Array(bits)) => Value::BitSequence(
+				bits.iter()
+					.map(|bit| bit.as_bool().ok_or_else(|| format!("Expected a bit, got {bit}")))
+					.collect::<Result<_, _>>()?,
+			),
+			(_, json) => return Err(format!("Value {json} doesn't match type {type_id}")),
+		};
+
+		Ok(value)
+	}
+
+	fn convert_json_variant(
+		&self,
+		types: &[&Type],
+		name: &str,
+		fields: &serde_json::Value,
+	) -> Result<Value, String> {
+		let variant = types
+			.iter()
+			.filter_map(|ty| ty.type_def.as_enumeration())
+			.find(|v| v.name == name)
+			.ok_or_else(|| format!("Unknown variant `{name}` of type {}", types[0].type_id.0))?;
+
+		Ok(Value::Variant {
+			name: name.into(),
+			index: variant.index.0 as u8,
+			fields: self
+				.convert_json_fields(fields, &variant.fields)
+				.map_err(|e| format!("Failed to convert variant `{name}`: {e}"))?,
+		})
+	}
+
+	fn convert_json_fields(
+		&self,
+		json: &serde_json::Value,
+		fields: &[Field],
+	) -> Result<Fields, String> {
+		use serde_json::Value as Json;
+
+		match json {
+			_ if fields.is_empty() => match json {
+				Json::Null => Ok(Vec::new()),
+				Json::Array(items) if items.is_empty() => Ok(Vec::new()),
+				Json::Object(map) if map.is_empty() => Ok(Vec::new()),
+				json => Err(format!("Expected no fields, got {json}")),
+			},
+			Json::Object(map) if !fields.is_empty() && fields.iter().all(|f| f.name.is_some()) => {
+				if let Some(name) =
+					map.keys().find(|n| !fields.iter().any(|f| f.name.as_ref() == Some(*n)))
+				{
+					return Err(format!("Unknown field `{name}`"))
+				}
+
+				fields
+					.iter()
+					.map(|field| {
+						let name =
+							field.name.as_ref().expect("Checked that all fields are named; qed");
+						let json =
+							map.get(name).ok_or_else(|| format!("Missing field `{name}`"))?;
+						let value = self
+							.convert_json(json, field.ty)
+							.map_err(|e| format!("Failed to convert field `{name}`: {e}"))?;
+
+						Ok((Some(name.clone()), value))
+					})
+					.collect()
+			},
+			json if fields.len() == 1 =>
+				Ok([(fields[0].name.clone(), self.convert_json(json, fields[0].ty)?)].into()),
+			Json::Array(items) if items.len() == fields.len() => items
+				.iter()
+				.zip(fields)
+				.map(|(item, field)| Ok((field.name.clone(), self.convert_json(item, field.ty)?)))
+				.collect(),
+			json => Err(format!("Expected {} fields, got {json}", fields.len())),
+		}
+	}
+
+	fn convert_json_items(
+		&self,
+		json: &serde_json::Value,
+		type_ref: TypeRef,
+	) -> Result<Vec<Value>, String> {
+		use serde_json::Value as Json;
+
+		match json {
+			Json::String(hex) if type_ref == TypeRef::U8 && hex.starts_with("0x") =>
+				Ok(array_bytes::hex2bytes(hex)
+					.map_err(|e| format!("Invalid hex string `{hex}`: {e:?}"))?
+					.into_iter()
+					.map(|b| Value::Unsigned(b.into()))
+					.collect()),
+			Json::Array(items) =>
+				items.iter().map(|item| self.convert_json(item, type_ref)).collect(),
+			json => Err(format!("Expected an array, got {json}")),
+		}
+	}
+}
+
+/// Returns the integer of the JSON number or decimal string `json`.
+#[cfg(feature = "json")]
+fn json_integer<T: core::str::FromStr + TryFrom<u64> + TryFrom<i64>>(
+	json: &serde_json::Value,
+) -> Result<T, String> {
+	let integer = match json {
+		serde_json::Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+			(Some(n), _) => T::try_from(n).ok(),
+			(_, Some(n)) => T::try_from(n).ok(),
+			_ => None,
+		},
+		serde_json::Value::String(n) => n.parse().ok(),
+		_ => None,
+	};
+
+	integer.ok_or_else(|| format!("Expected an integer, got {json}"))
+}
+
+/// Returns the little endian bytes of the 256bit integer of the JSON number or decimal string
+/// `json`.
+#[cfg(feature = "json")]
+fn json_integer_256(json: &serde_json::Value, signed: bool) -> Result<[u8; 32], String> {
+	let text = match json {
+		serde_json::Value::Number(n) => format!("{n}"),
+		serde_json::Value::String(n) => n.clone(),
+		json => return Err(format!("Expected an integer, got {json}")),
+	};
+	let (negative, digits) = match text.strip_prefix('-') {
+		Some(digits) if signed => (true, digits),
+		_ => (false, &text[..]),
+	};
+
+	if digits.is_empty() {
+		return Err(format!("Expected an integer, got {json}"))
+	}
+
+	let mut value = [0u8; 32];
+	for digit in digits.chars() {
+		let mut carry =
+			digit.to_digit(10).ok_or_else(|| format!("Expected an integer, got {json}"))?;
+
+		for byte in &mut value {
+			let next = *byte as u32 * 10 + carry;
+			*byte = next as u8;
+			carry = next >> 8;
+		}
+
+		if carry != 0 {
+			return Err(out_of_range(()))
+		}
+	}
+
+	if signed {
+		// The magnitude of a negative value may be `2^255`, of a positive one only `2^255 - 1`.
+		let min = value[31] == 0x80 && value[..31].iter().all(|b| *b == 0);
+		if value[31] & 0x80 != 0 && !(negative && min) {
+			return Err(out_of_range(()))
+		}
+
+		if negative {
+			// Two's complement.
+			let mut carry = 1;
+			for byte in &mut value {
+				let next = (!*byte) as u16 + carry;
+				*byte = next as u8;
+				carry = next >> 8;
+			}
+		}
+	}
+
+	Ok(value)
+}
+
+fn out_of_range<E>(_: E) -> String {
+	"Integer out of range".into()
+}
+
+fn unsigned(value: &Value) -> Result<u128, String> {
+	match value {
+		Value::Unsigned(v) => Ok(*v),
+		Value::Signed(v) => u128::try_from(*v).map_err(out_of_range),
+		v => Err(format!("Expected an unsigned integer, got {v:?}")),
+	}
+}
+
+fn signed(value: &Value) -> Result<i128, String> {
+	match value {
+		Value::Signed(v) => Ok(*v),
+		Value::Unsigned(v) => i128::try_from(*v).map_err(out_of_range),
+		v => Err(format!("Expected a signed integer, got {v:?}")),
+	}
+}
+
+/// Returns the little endian bytes of the unsigned 256bit integer `value`.
+fn u256(value: &Value) -> Result<[u8; 32], String> {
+	match value {
+		Value::U256(v) => Ok(*v),
+		value => {
+			let mut res = [0; 32];
+			res[..16].copy_from_slice(&unsigned(value)?.to_le_bytes());
+			Ok(res)
+		},
+	}
+}
+
+/// Encode the little endian `value` as `Compact<U256>`.
+///
+/// Uses the same encoding as the other compact encoded integers, see `decode_compact_u256` of
+/// the extrinsic decoder.
+fn encode_compact_u256(value: &[u8; 32], out: &mut Vec<u8>) {
+	let len = value.iter().rposition(|b| *b != 0).map_or(0, |p| p + 1);
+
+	if len <= 16 {
+		let mut bytes = [0; 16];
+		bytes.copy_from_slice(&value[..16]);
+		Compact(u128::from_le_bytes(bytes)).encode_to(out);
+	} else {
+		out.push((((len - 4) as u8) << 2) | 0b11);
+		out.extend(&value[..len]);
+	}
+}
+
+/// Encode the `bits` in the format of `bitvec`.
+fn encode_bit_sequence(
+	bits: &[bool],
+	bit_sequence: &TypeDefBitSequence,
+	out: &mut Vec<u8>,
+) -> Result<(), String> {
+	let num_bytes = bit_sequence.num_bytes as usize;
+	if !matches!(num_bytes, 1 | 2 | 4 | 8) {
+		return Err(format!("Unsupported number of bytes {num_bytes}"))
+	}
+
+	let unit_bits = num_bytes * 8;
+	Compact(bits.len() as u32).encode_to(out);
+
+	for unit in bits.chunks(unit_bits) {
+		let value = unit.iter().enumerate().filter(|(_, bit)| **bit).fold(0u64, |value, (i, _)| {
+			let shift =
+				if bit_sequence.least_significant_bit_first { i } else { unit_bits - 1 - i };
+			value | (1 << shift)
+		});
+
+		out.extend(&value.to_le_bytes()[..num_bytes]);
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		decode_extrinsic,
+		extrinsic_decoder::TypeResolver,
+		generate_proof_for_extrinsic, generate_type_information,
+		merkle_tree::tests::{TEST_ADDITIONAL_SIGNED, TEST_EXT},
+		tests::load_metadata,
+		types::{EnumerationVariant, TypeDefArray},
+		value::decode_value,
+	};
+	use alloc::vec;
+
+	#[test]
+	fn round_trip_works() {
+		let metadata = load_metadata("rococo_metadata_v15");
+		let extrinsic_metadata = generate_type_information(&metadata).unwrap().extrinsic_metadata;
+		let extrinsic = array_bytes::hex2bytes(TEST_EXT).unwrap();
+		let additional_signed = array_bytes::hex2bytes(TEST_ADDITIONAL_SIGNED).unwrap();
+		let proof =
+			generate_proof_for_extrinsic(&extrinsic, Some(&additional_signed), &metadata).unwrap();
+		let decoded =
+			decode_extrinsic(&extrinsic, Some(&additional_signed), &extrinsic_metadata, &proof)
+				.unwrap();
+
+		// Only the leaves of the proof are required for encoding the values again.
+		let call = encode_value(&decoded.call, extrinsic_metadata.call_ty, &proof.leaves).unwrap();
+		let (extra, signed_data) = encode_signed_extensions(
+			&decoded.signed_extensions,
+			&extrinsic_metadata,
+			&proof.leaves,
+		)
+		.unwrap();
+		assert_eq!(additional_signed, signed_data);
+
+		let signature = ExtrinsicSignature {
+			address: decoded.address.as_ref().unwrap(),
+			signature: decoded.signature.as_ref().unwrap(),
+			extra: &extra,
+		};
+		assert_eq!(
+			extrinsic,
+			encode_extrinsic(&call, Some(signature), &extrinsic_metadata, &proof.leaves).unwrap()
+		);
+		assert_eq!(decoded.signing_payload().unwrap(), [&call[..], &extra, &signed_data].concat());
+
+		// Signed extensions with an empty type can be omitted.
+		let mut signed_extensions = decoded.signed_extensions.clone();
+		signed_extensions.0.remove("CheckWeight");
+		assert_eq!(
+			(extra, signed_data),
+			encode_signed_extensions(&signed_extensions, &extrinsic_metadata, &proof.leaves)
+				.unwrap()
+		);
+		signed_extensions.0.remove("CheckNonce");
+		assert!(encode_signed_extensions(&signed_extensions, &extrinsic_metadata, &proof.leaves)
+			.is_err());
+	}
+
+	#[test]
+	fn encode_call_works() {
+		let metadata = load_metadata("rococo_metadata_v15");
+		let type_information = generate_type_information(&metadata).unwrap();
+		let extrinsic_metadata = &type_information.extrinsic_metadata;
+		let bytes = |bytes: &[u8]| {
+			Value::Sequence(bytes.iter().map(|b| Value::Unsigned(*b as u128)).collect())
+		};
+		let id = |account: &[u8]| Value::Variant {
+			name: "Id".into(),
+			index: 0,
+			fields: vec![(None, bytes(account))],
+		};
+		let encode_call = |pallet: &str, call: &str, args: Vec<(&str, Value)>| {
+			let args = args.into_iter().map(|(n, v)| (Some(n.into()), v)).collect();
+
+			encode_call(
+				pallet,
+				call,
+				args,
+				extrinsic_metadata.call_ty,
+				type_information.types.values(),
+			)
+		};
+
+		let call = encode_call(
+			"Balances",
+			"transfer_keep_alive",
+			vec![("value", Value::Unsigned(10)), ("dest", id(&[1; 32]))],
+		)
+		.unwrap();
+		let (balances, transfer_keep_alive) =
+			crate::tests::call_index(&type_information, "Balances", "transfer_keep_alive");
+		assert_eq!((balances, transfer_keep_alive, 0u8, [1u8; 32], Compact(10u128)).encode(), call);
+
+		let extrinsic =
+			encode_extrinsic(&call, None, extrinsic_metadata, type_information.types.values())
+				.unwrap();
+		let proof = generate_proof_for_extrinsic(&extrinsic, None, &metadata).unwrap();
+		let decoded = decode_extrinsic(&extrinsic, None, extrinsic_metadata, &proof).unwrap();
+		let decoded_call = &decoded.call.fields().unwrap()[0].1;
+		assert_eq!(Some("transfer_keep_alive"), decoded_call.variant_name());
+		assert_eq!(Some(10), decoded_call.field("value").and_then(Value::as_unsigned));
+		assert_eq!(Some(vec![1; 32]), decoded_call.field("dest").and_then(Value::as_account_id));
+
+		let err = |pallet, call, args| encode_call(pallet, call, args).unwrap_err();
+		assert!(err("Unknown", "remark", vec![]).contains("Unknown variant `Unknown`"));
+		assert!(err("System", "unknown", vec![]).contains("Unknown variant `unknown`"));
+		assert!(err("Balances", "transfer_keep_alive", vec![("dest", id(&[1; 32]))])
+			.contains("Missing field `value`"));
+		assert!(err(
+			"Balances",
+			"transfer_keep_alive",
+			vec![
+				("dest", id(&[1; 32])),
+				("value", Value::Unsigned(1)),
+				("other", Value::Bool(true))
+			]
+		)
+		.contains("Unknown field `other`"));
+		assert!(err(
+			"Balances",
+			"transfer_keep_alive",
+			vec![("dest", id(&[1; 31])), ("value", Value::Unsigned(1))]
+		)
+		.contains("Expected 32 items"));
+		assert!(err(
+			"System",
+			"remark",
+			vec![("remark", Value::Sequence(vec![Value::Unsigned(256)]))]
+		)
+		.contains("Integer out of range"));
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn value_from_json_works() {
+		use codec::Compact;
+		use serde_json::json;
+
+		let metadata = load_metadata("rococo_metadata_v15");
+		let type_information = generate_type_information(&metadata).unwrap();
+		let call_ty = type_information.extrinsic_metadata.call_ty;
+		let call_index = |pallet, call| crate::tests::call_index(&type_information, pallet, call);
+		let from_json = |json| value_from_json(&json, call_ty, type_information.types.values());
+		let encode = |json| {
+			encode_value(&from_json(json).unwrap(), call_ty, type_information.types.values())
+				.unwrap()
+		};
+		let account = array_bytes::bytes2hex("0x", [1u8; 32]);
+
+		assert_eq!(
+			(call_index("Balances", "transfer_keep_alive"), 0u8, [1u8; 32], Compact(10u128))
+				.encode(),
+			encode(json!({
+				"Balances": { "transfer_keep_alive": { "dest": { "Id": account }, "value": 10 } }
+			}))
+		);
+		// Integers that don't fit into a JSON number are decimal strings.
+		assert_eq!(
+			(call_index("Balances", "transfer_keep_alive"), 0u8, [1u8; 32], Compact(u128::MAX))
+				.encode(),
+			encode(json!({
+				"Balances": {
+					"transfer_keep_alive": { "dest": { "Id": account }, "value": u128::MAX.to_string() }
+				}
+			}))
+		);
+		// Variants without fields by name, nested calls and bytes as hex.
+		assert_eq!(
+			(
+				call_index("Proxy", "proxy"),
+				0u8,
+				[1u8; 32],
+				None::<u8>,
+				call_index("System", "remark"),
+				vec![1u8, 2]
+			)
+				.encode(),
+			encode(json!({
+				"Proxy": {
+					"proxy": {
+						"real": { "Id": account },
+						"force_proxy_type": "None",
+						"call": { "System": { "remark": "0x0102" } }
+					}
+				}
+			}))
+		);
+
+		let err = |json| from_json(json).unwrap_err();
+		assert!(err(json!({ "Unknown": {} })).contains("Unknown variant `Unknown`"));
+		assert!(err(json!({ "System": { "remark": { "other": "0x" } } }))
+			.contains("Unknown field `other`"));
+		assert!(err(json!({ "System": { "remark_with_event": {} } }))
+			.contains("Missing field `remark`"));
+		assert!(err(json!({
+			"Balances": { "transfer_keep_alive": { "dest": { "Id": account }, "value": 1.5 } }
+		}))
+		.contains("Expected an integer"));
+
+		// 256bit integers are converted from decimal strings of any size.
+		let integer = |json, type_ref| value_from_json(&json, type_ref, core::iter::empty());
+		let max = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+		let min = "-57896044618658097711785492504343953926634992332820282019728792003956564819968";
+		let (mut five, mut lowest) = ([0; 32], [0; 32]);
+		five[0] = 5;
+		lowest[31] = 0x80;
+		assert_eq!(Ok(Value::U256([0xff; 32])), integer(json!(max), TypeRef::CompactU256));
+		assert_eq!(Ok(Value::U256(five)), integer(json!(5), TypeRef::U256));
+		assert_eq!(Ok(Value::I256([0xff; 32])), integer(json!(-1), TypeRef::I256));
+		assert_eq!(Ok(Value::I256(lowest)), integer(json!(min), TypeRef::I256));
+		assert!(integer(json!(format!("{max}0")), TypeRef::U256).is_err());
+		assert!(integer(json!(&min[1..]), TypeRef::I256).is_err());
+		assert!(integer(json!(-1), TypeRef::U256).is_err());
+	}
+
+	#[test]
+	fn encode_primitives_works() {
+		let ty =
+			|type_id: u32, type_def| Type { path: Vec::new(), type_def, type_id: type_id.into() };
+		let types = [
+			ty(
+				0,
+				TypeDef::BitSequence(TypeDefBitSequence {
+					num_bytes: 1,
+					least_significant_bit_first: true,
+				}),
+			),
+			ty(
+				1,
+				TypeDef::BitSequence(TypeDefBitSequence {
+					num_bytes: 2,
+					least_significant_bit_first: false,
+				}),
+			),
+			ty(2, TypeDef::Tuple(vec![TypeRef::Str, TypeRef::Char, TypeRef::I256])),
+			ty(3, TypeDef::Array(TypeDefArray { len: 2, type_param: TypeRef::I8 })),
+			ty(
+				4,
+				TypeDef::Enumeration(EnumerationVariant {
+					name: "Variant".into(),
+					fields: Vec::new(),
+					index: 5u32.into(),
+				}),
+			),
+		];
+		let type_resolver = TypeResolver::new(types.iter());
+
+		let bits = [true, false, true, true, false, false, false, false, true, true];
+		let large = {
+			let mut large = [0u8; 32];
+			large[20] = 1;
+			large
+		};
+		let values = [
+			(0, Value::BitSequence(bits.to_vec())),
+			(1, Value::BitSequence(bits.to_vec())),
+			(
+				2,
+				Value::Sequence(vec![
+					Value::Str("RFC78".into()),
+					Value::Char('x'),
+					Value::I256([0xff; 32]),
+				]),
+			),
+			(
+				2,
+				Value::Sequence(vec![
+					Value::Str(String::new()),
+					Value::Char('y'),
+					Value::I256([0; 32]),
+				]),
+			),
+			(3, Value::Sequence(vec![Value::Signed(-128), Value::Signed(127)])),
+			(4, Value::Variant { name: "Variant".into(), index: 5, fields: Vec::new() }),
+		];
+
+		for (type_id, value) in values {
+			let type_ref = TypeRef::ById(type_id.into());
+			let encoded = encode_value(&value, type_ref, &types).unwrap();
+
+			assert_eq!(value, decode_value(&mut &encoded[..], type_ref, &type_resolver).unwrap());
+		}
+
+		assert_eq!(
+			vec![0b0000_1101, 0b0000_0011],
+			encode_value(&Value::BitSequence(bits.to_vec()), TypeRef::ById(0u32.into()), &types)
+				.unwrap()[1..]
+		);
+		assert!(encode_value(
+			&Value::Sequence(vec![Value::Signed(-129), Value::Signed(0)]),
+			TypeRef::ById(3u32.into()),
+			&types
+		)
+		.is_err());
+		// Small values of `Compact<U256>` use the same encoding as `Compact<u128>`.
+		assert_eq!(
+			Compact(u128::MAX).encode(),
+			encode_value(&Value::Unsigned(u128::MAX), TypeRef::CompactU256, &types).unwrap()
+		);
+		assert_eq!(
+			Compact(u64::MAX).encode(),
+			encode_value(&Value::Unsigned(u64::MAX.into()), TypeRef::CompactU256, &types).unwrap()
+		);
+		for value in [Value::U256(large), Value::U256([0xff; 32])] {
+			let encoded = encode_value(&value, TypeRef::CompactU256, &types).unwrap();
+
+			assert_eq!(
+				value,
+				decode_value(&mut &encoded[..], TypeRef::CompactU256, &type_resolver).unwrap()
+			);
+		}
+	}
+}
@@ -0,0 +1,354 @@
+//! Experimental functionality that is **not** part of RFC78.
+//!
+//! The digests and proofs generated by the functions in this module are **not** compatible with
+//! the ones generated by the rest of this crate or by any other implementation of RFC78. They
+//! exist to research better layouts of the merkle tree and should not be used in production.
+
+use alloc::{
+	collections::{BTreeMap, BTreeSet},
+	string::String,
+	vec::Vec,
+};
+
+use crate::{
+	extrinsic_decoder::decode_extrinsic_and_collect_type_ids,
+	from_frame_metadata::{FrameMetadataPrepared, TypeInformation},
+	merkle_tree::{MerkleTree, Proof, TypeId},
+	types::{MetadataDigest, Type},
+	ExtraInfo,
+};
+use frame_metadata::RuntimeMetadata;
+
+/// The order of the leaves in the merkle tree.
+#[derive(Debug, Clone, Copy)]
+pub enum LeafOrdering<'a> {
+	/// The order defined by RFC78, the leaves are sorted by their [`TypeId`].
+	Canonical,
+	/// Group the leaves by pallet.
+	///
+	/// The pallet is the first segment of the type path. Types without a path are put at the end.
+	/// Inside a group the canonical order is kept.
+	ByPallet,
+	/// Order the leaves by how often they are accessed when decoding the extrinsics in `corpus`.
+	///
+	/// The most accessed leaves come first, ties are broken by the canonical order.
+	AccessFrequency {
+		/// The encoded extrinsics.
+		corpus: &'a [&'a [u8]],
+	},
+	/// Order the leaves by a depth first traversal of the type graph.
+	///
+	/// The traversal starts at the call type, followed by the address, signature and signed
+	/// extension types. This puts types that reference each other next to each other.
+	TypeGraphLocality,
+}
+
+/// Generate the [`MetadataDigest`] using the given `extra_info` and `ordering`.
+///
+/// With [`LeafOrdering::Canonical`] this is the same as [`crate::generate_metadata_digest`].
+pub fn generate_metadata_digest(
+	metadata: &RuntimeMetadata,
+	extra_info: ExtraInfo,
+	ordering: LeafOrdering,
+) -> Result<MetadataDigest, String> {
+	let type_information = FrameMetadataPrepared::prepare(metadata)?.as_type_information()?;
+
+	let extrinsic_metadata_hash = type_information.extrinsic_metadata.hash();
+	let tree_root = MerkleTree::new(order_leaves(type_information, ordering)?).root();
+
+	Ok(extra_info.into_metadata_digest(tree_root, extrinsic_metadata_hash))
+}
+
+/// Generate a proof for the given `extrinsic` using the given `metadata` and `ordering`.
+///
+/// See [`crate::generate_proof_for_extrinsic`] for more information.
+pub fn generate_proof_for_extrinsic(
+	mut extrinsic: &[u8],
+	additional_signed: Option<&[u8]>,
+	metadata: &RuntimeMetadata,
+	ordering: LeafOrdering,
+) -> Result<Proof, String> {
+	let type_information = FrameMetadataPrepared::prepare(metadata)?.as_type_information()?;
+
+	let extrinsic = &mut extrinsic;
+
+	let accessed_types = decode_extrinsic_and_collect_type_ids(
+		extrinsic,
+		additional_signed,
+		&type_information.extrinsic_metadata,
+		type_information.types.values(),
+	)?;
+
+	if !extrinsic.is_empty() {
+		return Err("Bytes left in `extrinsic` after decoding".into());
+	}
+
+	MerkleTree::new(order_leaves(type_information, ordering)?).build_proof(accessed_types)
+}
+
+/// Returns the leaves of `type_information` in the given `ordering`.
+fn order_leaves(
+	mut type_information: TypeInformation,
+	ordering: LeafOrdering,
+) -> Result<Vec<(TypeId, Type)>, String> {
+	let order = leaf_order(&type_information, ordering)?;
+
+	Ok(order
+		.into_iter()
+		.map(|id| {
+			let ty =
+				type_information.types.remove(&id).expect("Order only contains known ids; qed");
+			(id, ty)
+		})
+		.collect())
+}
+
+/// Returns the [`TypeId`]s of all leaves in the given `ordering`.
+fn leaf_order(
+	type_information: &TypeInformation,
+	ordering: LeafOrdering,
+) -> Result<Vec<TypeId>, String> {
+	match ordering {
+		LeafOrdering::Canonical => Ok(type_information.types.keys().copied().collect()),
+		LeafOrdering::ByPallet => {
+			let mut by_pallet = BTreeMap::<&str, Vec<TypeId>>::new();
+			let mut without_path = Vec::new();
+
+			for (id, ty) in &type_information.types {
+				match ty.path.first() {
+					Some(pallet) => by_pallet.entry(pallet).or_default().push(*id),
+					None => without_path.push(*id),
+				}
+			}
+
+			Ok(by_pallet.into_values().flatten().chain(without_path).collect())
+		},
+		LeafOrdering::AccessFrequency { corpus } => {
+			let accessed = corpus
+				.iter()
+				.map(|extrinsic| {
+					decode_extrinsic_and_collect_type_ids(
+						&mut &extrinsic[..],
+						None,
+						&type_information.extrinsic_metadata,
+						type_information.types.values(),
+					)
+				})
+				.collect::<Result<Vec<_>, _>>()?;
+
+			Ok(order_by_access_frequency(type_information, accessed.iter().flatten()))
+		},
+		LeafOrdering::TypeGraphLocality => {
+			let extrinsic_metadata = &type_information.extrinsic_metadata;
+			let mut visited = BTreeSet::new();
+			let mut order = Vec::with_capacity(type_information.types.len());
+
+			let roots = [
+				extrinsic_metadata.call_ty,
+				extrinsic_metadata.address_ty,
+				extrinsic_metadata.signature_ty,
+			]
+			.into_iter()
+			.chain(
+				extrinsic_metadata
+					.signed_extensions
+					.iter()
+					.flat_map(|se| [se.included_in_extrinsic, se.included_in_signed_data]),
+			);
+
+			for root in roots {
+				type_information.visit_all_types(&root, &mut |id| {
+					let new = visited.insert(id);
+					if new {
+						order.push(id);
+					}
+					new
+				});
+			}
+
+			// All types should be reachable, but let's be sure to not lose any leaf.
+			order.extend(type_information.types.keys().filter(|id| !visited.contains(id)));
+
+			Ok(order)
+		},
+	}
+}
+
+/// Order the leaves by the number of times they appear in `accessed`.
+fn order_by_access_frequency<'a>(
+	type_information: &TypeInformation,
+	accessed: impl Iterator<Item = &'a TypeId>,
+) -> Vec<TypeId> {
+	let mut frequency = BTreeMap::<TypeId, usize>::new();
+	accessed.for_each(|id| *frequency.entry(*id).or_default() += 1);
+
+	let mut order = type_information.types.keys().copied().collect::<Vec<_>>();
+	// The sort is stable, so leaves with the same frequency keep the canonical order.
+	order.sort_by_key(|id| core::cmp::Reverse(frequency.get(id).copied().unwrap_or_default()));
+
+	order
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		generate_type_information,
+		merkle_tree::tests::{TEST_ADDITIONAL_SIGNED, TEST_EXT},
+		tests::{load_metadata, FIXTURES},
+		verify_proof,
+	};
+	use codec::Encode;
+
+	fn extra_info() -> ExtraInfo {
+		ExtraInfo {
+			spec_version: 1,
+			spec_name: "nice".into(),
+			base58_prefix: 1,
+			decimals: 1,
+			token_symbol: "lol".into(),
+		}
+	}
+
+	#[test]
+	fn canonical_ordering_matches_rfc() {
+		let metadata = load_metadata("rococo_metadata_v15");
+
+		assert_eq!(
+			crate::generate_metadata_digest(&metadata, extra_info()).unwrap(),
+			generate_metadata_digest(&metadata, extra_info(), LeafOrdering::Canonical).unwrap(),
+		);
+	}
+
+	#[test]
+	fn all_orderings_generate_valid_proofs() {
+		let metadata = load_metadata("rococo_metadata_v15");
+		let extrinsic = array_bytes::hex2bytes(TEST_EXT).unwrap();
+		let additional_signed = array_bytes::hex2bytes(TEST_ADDITIONAL_SIGNED).unwrap();
+		let type_information = generate_type_information(&metadata).unwrap();
+
+		for ordering in [
+			LeafOrdering::Canonical,
+			LeafOrdering::ByPallet,
+			LeafOrdering::AccessFrequency { corpus: &[&extrinsic] },
+			LeafOrdering::TypeGraphLocality,
+		] {
+			let order = leaf_order(&type_information, ordering).unwrap();
+			assert_eq!(type_information.types.len(), order.len());
+			assert_eq!(
+				type_information.types.keys().collect::<BTreeSet<_>>(),
+				order.iter().collect::<BTreeSet<_>>(),
+				"{ordering:?} must contain every leaf exactly once",
+			);
+
+			let proof = generate_proof_for_extrinsic(
+				&extrinsic,
+				Some(&additional_signed),
+				&metadata,
+				ordering,
+			)
+			.unwrap();
+
+			verify_proof(&extrinsic, Some(&additional_signed), &metadata, &proof).unwrap();
+		}
+	}
+
+	/// Returns the types accessed by every call of every pallet.
+	///
+	/// The types of the signed extensions are added to every call, as they are part of every signed
+	/// extrinsic. Nested calls are not followed, as they would add the entire call enum.
+	fn call_samples(type_information: &TypeInformation) -> Vec<BTreeSet<TypeId>> {
+		let extrinsic_metadata = &type_information.extrinsic_metadata;
+		let call_ty = extrinsic_metadata.call_ty.id().unwrap();
+
+		let mut signed_extensions = BTreeSet::new();
+		for se in &extrinsic_metadata.signed_extensions {
+			type_information.collect_all_types(&se.included_in_extrinsic, &mut signed_extensions);
+			type_information.collect_all_types(&se.included_in_signed_data, &mut signed_extensions);
+		}
+
+		let mut samples = Vec::new();
+
+		for (pallet_id, pallet) in type_information.types_by_id(call_ty) {
+			let Some(pallet_call_ty) = pallet
+				.type_def
+				.as_enumeration()
+				.and_then(|e| e.fields.first())
+				.and_then(|f| f.ty.id())
+			else {
+				continue;
+			};
+
+			for (call_id, call) in type_information.types_by_id(pallet_call_ty) {
+				let mut sample = signed_extensions.clone();
+				sample.insert(*pallet_id);
+				sample.insert(*call_id);
+
+				for field in call.type_def.as_enumeration().into_iter().flat_map(|e| &e.fields) {
+					type_information.visit_all_types(&field.ty, &mut |id| {
+						id.type_id() != call_ty && sample.insert(id)
+					});
+				}
+
+				samples.push(sample);
+			}
+		}
+
+		samples
+	}
+
+	/// Report the average proof sizes of the different orderings for every fixture.
+	///
+	/// The calls of every fixture are split in two halves. `AccessFrequency` is trained on the
+	/// first half and all orderings are scored on the second half. Run it with
+	/// `cargo test compare_leaf_orderings -- --ignored --nocapture`.
+	#[test]
+	#[ignore = "Report, prints the proof sizes of the orderings"]
+	fn compare_leaf_orderings() {
+		for (fixture, _) in FIXTURES {
+			let metadata = load_metadata(fixture);
+			let type_information = generate_type_information(&metadata).unwrap();
+			let (training, scoring): (Vec<_>, Vec<_>) = call_samples(&type_information)
+				.into_iter()
+				.enumerate()
+				.partition(|(i, _)| i % 2 == 0);
+
+			let average_proof_size = |order: Vec<TypeId>| {
+				let tree = MerkleTree::new(
+					order.into_iter().map(|id| (id, type_information.types[&id].clone())),
+				);
+
+				scoring
+					.iter()
+					.map(|(_, s)| tree.build_proof(s.iter().copied()).unwrap().encoded_size())
+					.sum::<usize>() / scoring.len()
+			};
+
+			let canonical =
+				average_proof_size(leaf_order(&type_information, LeafOrdering::Canonical).unwrap());
+			let sizes = [
+				("ByPallet", leaf_order(&type_information, LeafOrdering::ByPallet).unwrap()),
+				(
+					"AccessFrequency",
+					order_by_access_frequency(
+						&type_information,
+						training.iter().flat_map(|(_, s)| s),
+					),
+				),
+				(
+					"TypeGraphLocality",
+					leaf_order(&type_information, LeafOrdering::TypeGraphLocality).unwrap(),
+				),
+			]
+			.map(|(name, order)| (name, average_proof_size(order)));
+
+			std::println!("{fixture}: Canonical {canonical} bytes");
+			for (name, size) in sizes {
+				std::println!(
+					"{fixture}: {name} {size} bytes ({:+.1}%)",
+					(size as f64 - canonical as f64) * 100.0 / canonical as f64
+				);
+			}
+		}
+	}
+}
@@ -11,14 +11,16 @@ use scale_decode::{
 	ext::scale_type_resolver::{
 		BitsOrderFormat, BitsStoreFormat, Primitive as RPrimitive, ResolvedTypeVisitor, Variant,
 	},
-	visitor::{decode_with_visitor, DecodeError},
+	visitor::{decode_with_visitor, DecodeAsTypeResult, DecodeError, DecodeItemIterator},
 	Field, Visitor,
 };
 
 use crate::{
+	decoded_extrinsic::{DecodedExtrinsic, SignedExtensionValues},
 	from_frame_metadata::TypeInformation,
 	merkle_tree::TypeId,
-	types::{Type, TypeDef, TypeRef},
+	types::{ExtrinsicMetadata, SignedExtensionMetadata, Type, TypeDef, TypeRef},
+	value::decode_value,
 	SignedExtrinsicData,
 };
 
@@ -26,13 +28,13 @@ use crate::{
 /// to prevent a stack overflow.
 const MAX_STACK_DEPTH: usize = 1000;
 
-struct TypeResolver {
+pub(crate) struct TypeResolver {
 	raw_type_id_to_types: BTreeMap<u32, Vec<Type>>,
 	stack_depth: AtomicUsize,
 }
 
 impl TypeResolver {
-	fn new<'a>(types: impl Iterator<Item = &'a Type>) -> Self {
+	pub(crate) fn new<'a>(types: impl Iterator<Item = &'a Type>) -> Self {
 		Self {
 			raw_type_id_to_types: types.fold(Default::default(), |mut map, ty| {
 				map.entry(ty.type_id.0).or_default().push(ty.clone());
@@ -41,6 +43,13 @@ impl TypeResolver {
 			stack_depth: Default::default(),
 		}
 	}
+
+	/// Returns the path of the type `type_ref`, `None` for built-in types or unknown types.
+	pub(crate) fn path(&self, type_ref: TypeRef) -> Option<&[String]> {
+		let TypeRef::ById(id) = type_ref else { return None };
+
+		self.raw_type_id_to_types.get(&id.0)?.first().map(|ty| &ty.path[..])
+	}
 }
 
 impl scale_decode::TypeResolver for TypeResolver {
@@ -75,7 +84,9 @@ impl scale_decode::TypeResolver for TypeResolver {
 			TypeRef::CompactU32 => return Ok(visitor.visit_compact(TypeRef::U32)),
 			TypeRef::CompactU64 => return Ok(visitor.visit_compact(TypeRef::U64)),
 			TypeRef::CompactU128 => return Ok(visitor.visit_compact(TypeRef::U128)),
-			TypeRef::CompactU256 => return Err("CompactU256 not supported right now".into()),
+			// `scale-decode` doesn't support `Compact<U256>`, the visitor needs to decode it.
+			TypeRef::CompactU256 =>
+				return Err("`CompactU256` needs to be decoded by the visitor".into()),
 			TypeRef::Void =>
 				return Ok(visitor.visit_composite(core::iter::empty(), core::iter::empty())),
 		};
@@ -150,6 +161,75 @@ impl scale_decode::TypeResolver for TypeResolver {
 	}
 }
 
+/// Decode a compact encoded `U256`.
+///
+/// Returns the little endian representation of the decoded value. The same canonical encoding rules
+/// as for the other compact encoded integers apply.
+pub(crate) fn decode_compact_u256(input: &mut &[u8]) -> Result<[u8; 32], codec::Error> {
+	let prefix = input.read_byte()?;
+	let mut res = [0u8; 32];
+
+	match prefix % 4 {
+		0 => res[0] = prefix >> 2,
+		1 => {
+			let value = u16::from_le_bytes([prefix, input.read_byte()?]) >> 2;
+
+			if value <= 0b0011_1111 {
+				return Err("Out of range for `Compact<U256>`".into());
+			}
+
+			res[..2].copy_from_slice(&value.to_le_bytes());
+		},
+		2 => {
+			let mut bytes = [prefix, 0, 0, 0];
+			input.read(&mut bytes[1..])?;
+			let value = u32::from_le_bytes(bytes) >> 2;
+
+			if value <= 0b0011_1111_1111_1111 {
+				return Err("Out of range for `Compact<U256>`".into());
+			}
+
+			res[..4].copy_from_slice(&value.to_le_bytes());
+		},
+		_ => {
+			let len = (prefix >> 2) as usize + 4;
+
+			if len > res.len() {
+				return Err("Out of range for `Compact<U256>`".into());
+			}
+
+			input.read(&mut res[..len])?;
+
+			// The most significant byte must not be zero and the value must not fit into the
+			// previous mode.
+			if res[len - 1] == 0 || (len == 4 && res[3] < 0b0100_0000) {
+				return Err("Out of range for `Compact<U256>`".into());
+			}
+		},
+	}
+
+	Ok(res)
+}
+
+/// Decode the `remaining` items of `items` using the given `visitor`.
+///
+/// Iterating `items` directly would first decode each item using an internal visitor of
+/// `scale-decode` that isn't able to decode `CompactU256`. So, we need to use `decode_item`.
+pub(crate) fn decode_items<'scale, 'resolver, V>(
+	items: &mut impl DecodeItemIterator<'scale, 'resolver, TypeResolver>,
+	remaining: usize,
+	mut visitor: V,
+) -> Result<V, DecodeError>
+where
+	V: Visitor<TypeResolver = TypeResolver, Value<'scale, 'resolver> = V, Error = DecodeError>,
+{
+	for _ in 0..remaining {
+		visitor = items.decode_item(visitor).expect("There is at least one item remaining; qed")?;
+	}
+
+	Ok(visitor)
+}
+
 #[derive(Clone, Default)]
 struct CollectAccessedTypes {
 	accessed_types: BTreeSet<TypeId>,
@@ -158,31 +238,7 @@ struct CollectAccessedTypes {
 impl CollectAccessedTypes {
 	/// Collect all the types that are reachable from `type_ref`.
 	fn collect_all_types(&mut self, type_ref: &TypeRef, type_information: &TypeInformation) {
-		if let Some(id) = type_ref.id() {
-			type_information.types.iter().filter(|(k, _)| k.type_id() == id).for_each(
-				|(ty_id, ty)| {
-					if !self.accessed_types.insert(*ty_id) {
-						// We already recorded this type.
-						return;
-					}
-
-					match &ty.type_def {
-						TypeDef::Array(a) =>
-							self.collect_all_types(&a.type_param, type_information),
-						TypeDef::Composite(c) =>
-							c.iter().for_each(|f| self.collect_all_types(&f.ty, type_information)),
-						TypeDef::Enumeration(e) => e
-							.fields
-							.iter()
-							.for_each(|f| self.collect_all_types(&f.ty, type_information)),
-						TypeDef::Sequence(s) => self.collect_all_types(s, type_information),
-						TypeDef::Tuple(t) =>
-							t.iter().for_each(|t| self.collect_all_types(t, type_information)),
-						TypeDef::BitSequence(_) => {},
-					}
-				},
-			)
-		}
+		type_information.collect_all_types(type_ref, &mut self.accessed_types)
 	}
 }
 
@@ -191,6 +247,23 @@ impl Visitor for CollectAccessedTypes {
 	type Value<'scale, 'resolver> = Self;
 	type Error = DecodeError;
 
+	fn unchecked_decode_as_type<'scale, 'resolver>(
+		self,
+		input: &mut &'scale [u8],
+		type_id: TypeRef,
+		_types: &'resolver TypeResolver,
+	) -> DecodeAsTypeResult<Self, Result<Self::Value<'scale, 'resolver>, Self::Error>> {
+		if type_id == TypeRef::CompactU256 {
+			DecodeAsTypeResult::Decoded(
+				decode_compact_u256(input)
+					.map_err(Into::into)
+					.and_then(|value| self.visit_u256(&value, type_id)),
+			)
+		} else {
+			DecodeAsTypeResult::Skipped(self)
+		}
+	}
+
 	fn visit_bool<'scale, 'resolver>(
 		self,
 		_value: bool,
@@ -311,12 +384,8 @@ impl Visitor for CollectAccessedTypes {
 		self.accessed_types
 			.insert(TypeId::Other(type_id.id().expect("Sequence is always referenced by id; qed")));
 
-		let mut visitor = self;
-		for field in value.by_ref() {
-			visitor = field?.decode_with_visitor(visitor)?;
-		}
-
-		Ok(visitor)
+		let remaining = value.remaining();
+		decode_items(value, remaining, self)
 	}
 
 	fn visit_composite<'scale, 'resolver>(
@@ -333,12 +402,8 @@ impl Visitor for CollectAccessedTypes {
 			type_id.id().expect("Composite is always referenced by id; qed"),
 		));
 
-		let mut visitor = self;
-		for field in value.by_ref() {
-			visitor = field?.decode_with_visitor(visitor)?;
-		}
-
-		Ok(visitor)
+		let remaining = value.remaining();
+		decode_items(value, remaining, self)
 	}
 
 	fn visit_tuple<'scale, 'resolver>(
@@ -349,12 +414,8 @@ impl Visitor for CollectAccessedTypes {
 		self.accessed_types
 			.insert(TypeId::Other(type_id.id().expect("Tuple is always referenced by id; qed")));
 
-		let mut visitor = self;
-		for field in value.by_ref() {
-			visitor = field?.decode_with_visitor(visitor)?;
-		}
-
-		Ok(visitor)
+		let remaining = value.remaining();
+		decode_items(value, remaining, self)
 	}
 
 	fn visit_str<'scale, 'resolver>(
@@ -375,12 +436,8 @@ impl Visitor for CollectAccessedTypes {
 			variant: value.index() as u32,
 		});
 
-		let mut visitor = self;
-		while let Some(field) = value.fields().next() {
-			visitor = field?.decode_with_visitor(visitor)?;
-		}
-
-		Ok(visitor)
+		let remaining = value.fields().remaining();
+		decode_items(value.fields(), remaining, self)
 	}
 
 	fn visit_array<'scale, 'resolver>(
@@ -392,12 +449,8 @@ impl Visitor for CollectAccessedTypes {
 			type_id.id().expect("BitSequence is always referenced by id; qed"),
 		));
 
-		let mut visitor = self;
-		for field in value.by_ref() {
-			visitor = field?.decode_with_visitor(visitor)?;
-		}
-
-		Ok(visitor)
+		let remaining = value.remaining();
+		decode_items(value, remaining, self)
 	}
 
 	fn visit_bitsequence<'scale, 'resolver>(
@@ -412,14 +465,41 @@ impl Visitor for CollectAccessedTypes {
 	}
 }
 
-pub fn decode_extrinsic_and_collect_type_ids<'a>(
-	extrinsic: &mut &[u8],
-	additional_signed: Option<&[u8]>,
-	type_information: &TypeInformation,
-	types: impl Iterator<Item = &'a Type>,
-) -> Result<Vec<TypeId>, String> {
-	let type_resolver = TypeResolver::new(types);
+/// A part of an extrinsic or of the additional signed data.
+pub(crate) enum ExtrinsicPart<'a> {
+	Address,
+	Signature,
+	/// The data of a signed extension that is included in the extrinsic.
+	Extra(&'a SignedExtensionMetadata),
+	Call,
+	/// The data of a signed extension that is included in the signed data.
+	SignedData(&'a SignedExtensionMetadata),
+}
+
+impl ExtrinsicPart<'_> {
+	/// Returns the error for failing to decode this part.
+	fn error(&self, error: String) -> String {
+		match self {
+			Self::Address => format!("Failed to decode address: {error}"),
+			Self::Signature => format!("Failed to decode signature: {error}"),
+			Self::Extra(se) | Self::SignedData(se) =>
+				format!("Failed to decode extra ({}): {error}", se.identifier),
+			Self::Call => format!("Failed to decode call: {error}"),
+		}
+	}
+}
 
+/// Decode the header of `extrinsic`, followed by all its parts.
+///
+/// `decode` is called for every part in the order the parts are encoded, with the type to decode
+/// the part as. It returns the new `state`. The address, the signature and the extra are only
+/// part of signed extrinsics. Returns the final `state` and whether the extrinsic is signed.
+pub(crate) fn decode_extrinsic_parts<'scale, S>(
+	extrinsic: &mut &'scale [u8],
+	extrinsic_metadata: &ExtrinsicMetadata,
+	state: S,
+	mut decode: impl FnMut(S, &mut &'scale [u8], &ExtrinsicPart, TypeRef) -> Result<S, String>,
+) -> Result<(S, bool), String> {
 	let _length =
 		Compact::<u32>::decode(extrinsic).map_err(|e| format!("Failed to read length: {e}"))?;
 
@@ -433,61 +513,85 @@ pub fn decode_extrinsic_and_collect_type_ids<'a>(
 		return Err("Invalid transaction version".into());
 	}
 
-	let visitor = is_signed
+	let signed_parts = is_signed
 		.then(|| {
-			let visitor = decode_with_visitor(
-				extrinsic,
-				type_information.extrinsic_metadata.address_ty,
-				&type_resolver,
-				CollectAccessedTypes::default(),
+			[
+				(ExtrinsicPart::Address, extrinsic_metadata.address_ty),
+				(ExtrinsicPart::Signature, extrinsic_metadata.signature_ty),
+			]
+			.into_iter()
+			.chain(
+				extrinsic_metadata
+					.signed_extensions
+					.iter()
+					.map(|se| (ExtrinsicPart::Extra(se), se.included_in_extrinsic)),
 			)
-			.map_err(|e| format!("Failed to decode address: {e}"))?;
+		})
+		.into_iter()
+		.flatten();
 
-			let visitor = decode_with_visitor(
-				extrinsic,
-				type_information.extrinsic_metadata.signature_ty,
-				&type_resolver,
-				visitor,
-			)
-			.map_err(|e| format!("Failed to decode signature: {e}"))?;
+	let state = signed_parts
+		.chain([(ExtrinsicPart::Call, extrinsic_metadata.call_ty)])
+		.try_fold(state, |state, (part, ty)| {
+			decode(state, extrinsic, &part, ty).map_err(|e| part.error(e))
+		})?;
 
-			type_information.extrinsic_metadata.signed_extensions.iter().try_fold(
-				visitor,
-				|visitor, se| {
-					decode_with_visitor(
-						extrinsic,
-						se.included_in_extrinsic,
-						&type_resolver,
-						visitor,
-					)
-					.map_err(|e| format!("Failed to decode extra ({}): {e}", se.identifier))
-				},
-			)
-		})
-		.transpose()?
-		.unwrap_or_default();
+	Ok((state, is_signed))
+}
+
+/// Decode the data of all signed extensions that is included in the signed data.
+///
+/// See [`decode_extrinsic_parts`] for `decode`. Bytes left in `additional_signed` are not
+/// checked.
+pub(crate) fn decode_signed_data_parts<'scale, S>(
+	additional_signed: &mut &'scale [u8],
+	extrinsic_metadata: &ExtrinsicMetadata,
+	state: S,
+	mut decode: impl FnMut(S, &mut &'scale [u8], &ExtrinsicPart, TypeRef) -> Result<S, String>,
+) -> Result<S, String> {
+	extrinsic_metadata.signed_extensions.iter().try_fold(state, |state, se| {
+		let part = ExtrinsicPart::SignedData(se);
+
+		decode(state, additional_signed, &part, se.included_in_signed_data)
+			.map_err(|e| part.error(e))
+	})
+}
+
+/// Returns a `decode` function for [`decode_extrinsic_parts`] that decodes every part using the
+/// visitor that is passed as state.
+pub(crate) fn decode_with<'scale, 'resolver, V>(
+	type_resolver: &'resolver TypeResolver,
+) -> impl FnMut(V, &mut &'scale [u8], &ExtrinsicPart, TypeRef) -> Result<V, String> + 'resolver
+where
+	V: Visitor<TypeResolver = TypeResolver, Value<'scale, 'resolver> = V, Error = DecodeError>,
+{
+	move |visitor, input, _, type_ref| {
+		decode_with_visitor(input, type_ref, type_resolver, visitor).map_err(|e| format!("{e}"))
+	}
+}
 
-	let visitor = decode_with_visitor(
+pub fn decode_extrinsic_and_collect_type_ids<'a>(
+	extrinsic: &mut &[u8],
+	additional_signed: Option<&[u8]>,
+	extrinsic_metadata: &ExtrinsicMetadata,
+	types: impl Iterator<Item = &'a Type>,
+) -> Result<Vec<TypeId>, String> {
+	let type_resolver = TypeResolver::new(types);
+
+	let (visitor, _) = decode_extrinsic_parts(
 		extrinsic,
-		type_information.extrinsic_metadata.call_ty,
-		&type_resolver,
-		visitor,
-	)
-	.map_err(|e| format!("Failed to decode call: {e}"))?;
+		extrinsic_metadata,
+		CollectAccessedTypes::default(),
+		decode_with(&type_resolver),
+	)?;
 
 	let visitor = additional_signed
 		.map(|mut additional| {
-			type_information.extrinsic_metadata.signed_extensions.iter().try_fold(
+			decode_signed_data_parts(
+				&mut additional,
+				extrinsic_metadata,
 				visitor.clone(),
-				|visitor, se| {
-					decode_with_visitor(
-						&mut additional,
-						se.included_in_signed_data,
-						&type_resolver,
-						visitor,
-					)
-					.map_err(|e| format!("Failed to decode extra ({}): {e}", se.identifier))
-				},
+				decode_with(&type_resolver),
 			)
 		})
 		.unwrap_or_else(|| Ok(visitor))?;
@@ -495,6 +599,88 @@ pub fn decode_extrinsic_and_collect_type_ids<'a>(
 	Ok(visitor.accessed_types.into_iter().collect())
 }
 
+pub fn decode_extrinsic_values<'a>(
+	extrinsic: &mut &[u8],
+	additional_signed: Option<&[u8]>,
+	extrinsic_metadata: &ExtrinsicMetadata,
+	types: impl Iterator<Item = &'a Type>,
+) -> Result<DecodedExtrinsic, String> {
+	let type_resolver = TypeResolver::new(types);
+
+	let mut signed_extensions = SignedExtensionValues::default();
+	let (mut address, mut signature, mut call) = (None, None, None);
+	let mut extra = Vec::new();
+	let mut encoded_call: &[u8] = &[];
+
+	let ((), is_signed) =
+		decode_extrinsic_parts(extrinsic, extrinsic_metadata, (), |(), input, part, type_ref| {
+			let start = *input;
+			let value = decode_value(input, type_ref, &type_resolver)?;
+			let encoded = &start[..start.len() - input.len()];
+
+			match part {
+				ExtrinsicPart::Address => address = Some(value),
+				ExtrinsicPart::Signature => signature = Some(value),
+				ExtrinsicPart::Extra(se) => {
+					extra.extend_from_slice(encoded);
+					signed_extensions
+						.0
+						.entry(se.identifier.clone())
+						.or_default()
+						.included_in_extrinsic = Some(value);
+				},
+				ExtrinsicPart::Call => {
+					encoded_call = encoded;
+					call = Some(value);
+				},
+				ExtrinsicPart::SignedData(_) => {},
+			}
+
+			Ok(())
+		})?;
+
+	let mut signing_payload = None;
+
+	if let Some(mut additional) = additional_signed {
+		let additional_start = additional;
+
+		decode_signed_data_parts(
+			&mut additional,
+			extrinsic_metadata,
+			(),
+			|(), input, part, type_ref| {
+				let value = decode_value(input, type_ref, &type_resolver)?;
+
+				if let ExtrinsicPart::SignedData(se) = part {
+					signed_extensions
+						.0
+						.entry(se.identifier.clone())
+						.or_default()
+						.included_in_signed_data = Some(value);
+				}
+
+				Ok(())
+			},
+		)?;
+
+		if !additional.is_empty() {
+			return Err("Bytes left in `additional_signed` after decoding".into());
+		}
+
+		// Only the decoded bytes are part of the payload, to never sign bytes that weren't decoded.
+		let additional = &additional_start[..additional_start.len() - additional.len()];
+		signing_payload = is_signed.then(|| [encoded_call, &extra, additional].concat());
+	}
+
+	Ok(DecodedExtrinsic {
+		address,
+		signature,
+		signed_extensions,
+		call: call.expect("The call is always decoded; qed"),
+		signing_payload,
+	})
+}
+
 pub fn decode_extrinsic_parts_and_collect_type_ids<'a>(
 	call: &mut &[u8],
 	signed_ext_data: Option<SignedExtrinsicData>,
@@ -556,3 +742,173 @@ pub fn decode_extrinsic_parts_and_collect_type_ids<'a>(
 
 	Ok(visitor.accessed_types.into_iter().collect())
 }
+
+pub fn decode_value_and_collect_type_ids<'a>(
+	value: &mut &[u8],
+	type_ref: TypeRef,
+	types: impl Iterator<Item = &'a Type>,
+) -> Result<Vec<TypeId>, String> {
+	let type_resolver = TypeResolver::new(types);
+
+	let visitor =
+		decode_with_visitor(value, type_ref, &type_resolver, CollectAccessedTypes::default())
+			.map_err(|e| format!("Failed to decode value: {e}"))?;
+
+	Ok(visitor.accessed_types.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{generate_proof_for_extrinsic, verify_proof};
+	use codec::Encode;
+	use frame_metadata::{
+		v15::{CustomMetadata, ExtrinsicMetadata, OuterEnums, RuntimeMetadataV15},
+		RuntimeMetadata,
+	};
+	use scale_info::{
+		form::PortableForm, Field, Path, PortableRegistryBuilder, TypeDefCompact, TypeDefPrimitive,
+		TypeDefTuple, TypeDefVariant, Variant,
+	};
+
+	/// Build the metadata of a runtime with a `RuntimeCall` that only has
+	/// `Evm::transfer { value: Compact<U256> }`.
+	fn compact_u256_metadata() -> RuntimeMetadata {
+		let mut builder = PortableRegistryBuilder::new();
+
+		let ty = |path: Vec<&str>, type_def: scale_info::TypeDef<PortableForm>| {
+			scale_info::Type::new(
+				Path::from_segments_unchecked(path.into_iter().map(Into::into)),
+				Vec::new(),
+				type_def,
+				Vec::new(),
+			)
+		};
+
+		let u256 = builder.register_type(ty(Vec::new(), TypeDefPrimitive::U256.into()));
+		let compact_u256 =
+			builder.register_type(ty(Vec::new(), TypeDefCompact::new(u256.into()).into()));
+		let u8 = builder.register_type(ty(Vec::new(), TypeDefPrimitive::U8.into()));
+		let unit = builder.register_type(ty(Vec::new(), TypeDefTuple::new_portable([]).into()));
+		let evm_call = builder.register_type(ty(
+			vec!["pallet_evm", "pallet", "Call"],
+			TypeDefVariant::new([Variant::new(
+				"transfer".into(),
+				vec![Field::new(
+					Some("value".into()),
+					compact_u256.into(),
+					Some("U256".into()),
+					Vec::new(),
+				)],
+				0,
+				Vec::new(),
+			)])
+			.into(),
+		));
+		let runtime_call = builder.register_type(ty(
+			vec!["runtime", "RuntimeCall"],
+			TypeDefVariant::new([Variant::new(
+				"Evm".into(),
+				vec![Field::new(None, evm_call.into(), None, Vec::new())],
+				0,
+				Vec::new(),
+			)])
+			.into(),
+		));
+
+		RuntimeMetadata::V15(RuntimeMetadataV15 {
+			types: builder.finish(),
+			pallets: Vec::new(),
+			extrinsic: ExtrinsicMetadata {
+				version: 4,
+				address_ty: u8.into(),
+				call_ty: runtime_call.into(),
+				signature_ty: u8.into(),
+				extra_ty: unit.into(),
+				signed_extensions: Vec::new(),
+			},
+			ty: unit.into(),
+			apis: Vec::new(),
+			outer_enums: OuterEnums {
+				call_enum_ty: runtime_call.into(),
+				event_enum_ty: unit.into(),
+				error_enum_ty: unit.into(),
+			},
+			custom: CustomMetadata { map: Default::default() },
+		})
+	}
+
+	/// Compact encode the given little endian `U256`.
+	fn encode_compact_u256(value: [u8; 32]) -> Vec<u8> {
+		let len = value.iter().rposition(|b| *b != 0).map_or(0, |p| p + 1);
+
+		if len <= 16 {
+			let mut bytes = [0u8; 16];
+			bytes.copy_from_slice(&value[..16]);
+			Compact(u128::from_le_bytes(bytes)).encode()
+		} else {
+			let mut res = vec![((len - 4) as u8) << 2 | 0b11];
+			res.extend_from_slice(&value[..len]);
+			res
+		}
+	}
+
+	#[test]
+	fn decode_compact_u256_works() {
+		let mut partial = [0xff; 32];
+		partial[20..].fill(0);
+
+		let values =
+			[0u128, 1, 63, 64, 16383, 16384, (1 << 30) - 1, 1 << 30, u64::MAX as u128, u128::MAX]
+				.into_iter()
+				.map(|v| {
+					let mut res = [0u8; 32];
+					res[..16].copy_from_slice(&v.to_le_bytes());
+					res
+				})
+				.chain([[0x11; 32], [0xff; 32], partial]);
+
+		for value in values {
+			let encoded = encode_compact_u256(value);
+			let input = &mut &encoded[..];
+
+			assert_eq!(value, decode_compact_u256(input).unwrap());
+			assert!(input.is_empty());
+		}
+
+		// Non canonical encodings are rejected.
+		assert!(decode_compact_u256(&mut &[0b0000_0001, 0][..]).is_err());
+		assert!(decode_compact_u256(&mut &[0b1111_1110, 0xff, 0, 0][..]).is_err());
+		assert!(decode_compact_u256(&mut &[0b0000_0011, 0, 0, 0, 0b0011_1111][..]).is_err());
+		assert!(decode_compact_u256(&mut &[0b0000_0111, 1, 0, 0, 0, 0][..]).is_err());
+		// More than `32` bytes.
+		assert!(decode_compact_u256(&mut &[0b0111_0111; 34][..]).is_err());
+		// Not enough input.
+		assert!(decode_compact_u256(&mut &[0b0111_0011; 32][..]).is_err());
+	}
+
+	#[test]
+	fn extrinsic_with_compact_u256_works() {
+		let metadata = compact_u256_metadata();
+
+		for value in [[0u8; 32], [0xff; 32], {
+			let mut v = [0u8; 32];
+			v[..3].copy_from_slice(&[1, 2, 3]);
+			v
+		}] {
+			// `Evm::transfer`
+			let mut call = vec![0, 0];
+			call.extend(encode_compact_u256(value));
+
+			// Unsigned extrinsic with version `4`.
+			let mut extrinsic = Compact(call.len() as u32 + 1).encode();
+			extrinsic.push(4);
+			extrinsic.extend(&call);
+
+			let proof = generate_proof_for_extrinsic(&extrinsic, None, &metadata).unwrap();
+			assert_eq!(2, proof.leaves.len());
+
+			verify_proof(&extrinsic, None, &metadata, &proof).unwrap();
+		}
+	}
+}
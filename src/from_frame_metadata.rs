@@ -17,13 +17,111 @@ use scale_info::{
 use crate::{merkle_tree::TypeId, types};
 
 /// The type information generated from the FRAME metadata.
+#[derive(Debug, Clone)]
 pub struct TypeInformation {
 	/// The extrinsic metadata in the final form.
 	pub extrinsic_metadata: types::ExtrinsicMetadata,
 	/// All the types.
 	pub types: BTreeMap<TypeId, types::Type>,
+	/// The [`TypeRef`](types::TypeRef) of every accessible type in the `PortableRegistry`.
+	frame_type_refs: BTreeMap<u32, types::TypeRef>,
+	/// The id in the `PortableRegistry` of every type in `types`.
+	frame_type_ids: BTreeMap<u32, u32>,
 }
 
+impl TypeInformation {
+	/// Returns the [`TypeRef`](types::TypeRef) of the type with `frame_type_id`.
+	///
+	/// `frame_type_id` is the id of the type in the `PortableRegistry` of the metadata. Returns
+	/// `None` if the type is not reachable from the extrinsic metadata.
+	pub fn frame_type_ref(&self, frame_type_id: u32) -> Option<types::TypeRef> {
+		self.frame_type_refs.get(&frame_type_id).copied()
+	}
+
+	/// Returns the id in the `PortableRegistry` of the metadata of the type with `type_id`.
+	///
+	/// This is the reverse of [`Self::frame_type_ref`] for types referenced by
+	/// [`TypeRef::ById`](types::TypeRef::ById).
+	pub fn frame_type_id(&self, type_id: u32) -> Option<u32> {
+		self.frame_type_ids.get(&type_id).copied()
+	}
+
+	/// Returns the ids of all the types with the given `path`.
+	///
+	/// Returns `(frame_type_id, type_id)` pairs, where `frame_type_id` is the id in the
+	/// `PortableRegistry` of the metadata. Generic types share the same path and thus, there can
+	/// be multiple types per path.
+	pub fn type_ids_by_path<'a>(
+		&'a self,
+		path: &'a [&str],
+	) -> impl Iterator<Item = (u32, u32)> + 'a {
+		self.types
+			.values()
+			.filter(move |ty| ty.path.iter().map(String::as_str).eq(path.iter().copied()))
+			.map(|ty| ty.type_id.0)
+			.collect::<BTreeSet<_>>()
+			.into_iter()
+			.filter_map(|type_id| Some((self.frame_type_id(type_id)?, type_id)))
+	}
+
+	/// Returns all the types with the given `type_id`.
+	///
+	/// For enumerations this returns one type per variant.
+	pub fn types_by_id(&self, type_id: u32) -> impl Iterator<Item = (&TypeId, &types::Type)> {
+		// `TypeId::Other` is ordered by the `type_id` only and thus, it is part of this range.
+		self.types.range(
+			TypeId::Enumeration { type_id, variant: 0 }..=TypeId::Enumeration {
+				type_id,
+				variant: u32::MAX,
+			},
+		)
+	}
+
+	/// Collect all the types that are reachable from `type_ref` into `collected`.
+	///
+	/// Types that are already part of `collected` are not visited again.
+	pub(crate) fn collect_all_types(
+		&self,
+		type_ref: &types::TypeRef,
+		collected: &mut BTreeSet<TypeId>,
+	) {
+		self.visit_all_types(type_ref, &mut |id| collected.insert(id))
+	}
+
+	/// Visit all the types that are reachable from `type_ref` in depth first order.
+	///
+	/// `visit` is called for every type and should return `true` if the children of the type
+	/// should be visited as well. Returning `false` is required for types that were already
+	/// visited, otherwise recursive types are visited forever.
+	pub(crate) fn visit_all_types(
+		&self,
+		type_ref: &types::TypeRef,
+		visit: &mut impl FnMut(TypeId) -> bool,
+	) {
+		let Some(id) = type_ref.id() else { return };
+
+		for (ty_id, ty) in self.types_by_id(id) {
+			if !visit(*ty_id) {
+				continue;
+			}
+
+			match &ty.type_def {
+				types::TypeDef::Array(a) => self.visit_all_types(&a.type_param, visit),
+				types::TypeDef::Composite(c) =>
+					c.iter().for_each(|f| self.visit_all_types(&f.ty, visit)),
+				types::TypeDef::Enumeration(e) =>
+					e.fields.iter().for_each(|f| self.visit_all_types(&f.ty, visit)),
+				types::TypeDef::Sequence(s) => self.visit_all_types(s, visit),
+				types::TypeDef::Tuple(t) => t.iter().for_each(|t| self.visit_all_types(t, visit)),
+				types::TypeDef::BitSequence(_) => {},
+			}
+		}
+	}
+}
+
+/// The `metadata` prepared for generating the [`TypeInformation`].
+///
+/// Contains all the types that are accessible from the extrinsic metadata.
 pub struct FrameMetadataPrepared {
 	accessible_types: BTreeSet<u32>,
 	frame_type_registry: PortableRegistry,
@@ -31,6 +129,9 @@ pub struct FrameMetadataPrepared {
 }
 
 impl FrameMetadataPrepared {
+	/// Prepare the given `metadata`.
+	///
+	/// Only metadata V15 is supported.
 	pub fn prepare(metadata: &RuntimeMetadata) -> Result<Self, String> {
 		let metadata = match metadata {
 			RuntimeMetadata::V15(m) => m,
@@ -74,6 +175,9 @@ impl FrameMetadataPrepared {
 		&self.frame_type_registry.types[id as usize].ty
 	}
 
+	/// Generate the [`TypeInformation`].
+	///
+	/// All accessible types are renumbered to get dense type ids.
 	pub fn as_type_information(&self) -> Result<TypeInformation, String> {
 		let mut next_id = 0;
 		let frame_id_to_id = self
@@ -93,10 +197,11 @@ impl FrameMetadataPrepared {
 		};
 
 		let extrinsic_metadata = self.extrinsic_metadata.as_basic_type(type_context)?;
-		let types =
-			frame_id_to_id.iter().try_fold(BTreeMap::default(), |mut res, (frame_id, id)| {
-				res.extend(self.get_type(*frame_id).as_basic_type(type_context)?.into_iter().map(
-					|mut ty| {
+		let convert_type = |(frame_id, id): (&u32, &u32)| {
+			self.get_type(*frame_id).as_basic_type(type_context).map(|types| {
+				types
+					.into_iter()
+					.map(|mut ty| {
 						ty.type_id = id.into();
 
 						let id = if let Some(variant) = ty.type_def.as_enumeration() {
@@ -106,13 +211,42 @@ impl FrameMetadataPrepared {
 						};
 
 						(id, ty)
-					},
-				));
+					})
+					.collect::<Vec<_>>()
+			})
+		};
 
-				Ok::<_, String>(res)
-			})?;
+		#[cfg(feature = "parallel")]
+		let types = {
+			use rayon::prelude::*;
+
+			frame_id_to_id
+				.par_iter()
+				.map(convert_type)
+				.collect::<Result<Vec<_>, String>>()?
+		};
+		#[cfg(not(feature = "parallel"))]
+		let types = frame_id_to_id.iter().map(convert_type).collect::<Result<Vec<_>, String>>()?;
+
+		let types = types.into_iter().flatten().collect();
+
+		// Types that are not basic types are not part of the merkle tree, but may still be
+		// referenced, e.g. primitives.
+		let frame_type_refs = self
+			.accessible_types
+			.iter()
+			.map(|id| match frame_id_to_id.get(id) {
+				Some(new_id) => Ok((*id, types::TypeRef::ById(new_id.into()))),
+				None => UntrackedSymbol::<core::any::TypeId>::from(*id)
+					.as_basic_type_ref(type_context)
+					.map(|type_ref| (*id, type_ref)),
+			})
+			.collect::<Result<_, String>>()?;
+
+		let frame_type_ids =
+			frame_id_to_id.into_iter().map(|(frame_id, id)| (id, frame_id)).collect();
 
-		Ok(TypeInformation { extrinsic_metadata, types })
+		Ok(TypeInformation { extrinsic_metadata, types, frame_type_refs, frame_type_ids })
 	}
 }
 
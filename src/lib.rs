@@ -15,27 +15,105 @@
 //!   full extrinsic to be passed. However, this may increases the proof size, see the documentation
 //!   of the function for more information.
 //!
+//! - [`generate_proof_for_value`]: Generates a proof for a value of any type that is reachable from
+//!   the extrinsic metadata, e.g. a call that is nested in another call as opaque bytes.
+//!
+//! - [`generate_proof_for_call_template`]: Generates a proof for a call that doesn't depend on the
+//!   values of its arguments and can thus, be reused for any extrinsic with this call.
+//!
+//! - [`decode_extrinsic`]: Decodes an extrinsic using the leaves of a proof and returns the decoded
+//!   values, e.g. of the signed extensions.
+//!
+//! - [`encode_call`]: Encodes a call from the pallet name, the call name and the values of the
+//!   arguments. Together with [`encode_signed_extensions`] and [`encode_extrinsic`] this builds
+//!   extrinsics that can be decoded by [`decode_extrinsic`].
+//!
+//! - [`decode_call_tree`]: Decodes an extrinsic using the leaves of a proof and returns the tree of
+//!   the calls in it, e.g. the calls in a `Utility.batch_all`.
+//!
+//! - [`TypeInformation::lookup_call`]: Returns the indices and the arguments of a call by the name
+//!   of its pallet and its name. [`TypeInformation::pallets`] lists all pallets and their calls.
+//!
+//! - [`Policy::evaluate`]: Decides using allow and deny rules whether the calls of an extrinsic,
+//!   including the nested ones, are allowed.
+//!
+//! - [`Renderers::render`]: Renders a decoded value for humans, e.g. a `Perbill` as percentage.
+//!   Renderers for chain specific types can be registered.
+//!
+//! - [`Proof::size_report`]: Returns the size of a proof and a breakdown of where the bytes are
+//!   coming from.
+//!
+//! - [`generate_metadata_diff`]: Returns the types that changed between two versions of the
+//!   `metadata`.
+//!
 //! These functions are the main entry point providing all the functionality expected from this
 //! crate. The crate itself is by default `no_std` compatible.
+//!
+//! # Features
+//!
+//! - `std`: Enables the `std` features of the dependencies.
+//!
+//! - `parallel`: Parallelizes the conversion of the types and the construction of the merkle tree.
+//!   The generated digests and proofs are the same as without this feature. Requires `std`.
+//!
+//! - `signature-verification`: Enables `verify_signature` for verifying the signature of a decoded
+//!   extrinsic.
+//!
+//! - `json`: Enables `value_from_json` for converting JSON to the values of a call.
 
-#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
 
 extern crate alloc;
 
-use alloc::string::String;
+use alloc::{collections::BTreeSet, format, string::String, vec::Vec};
+use call_tree::collect_call_tree;
+pub use call_tree::CallNode;
+pub use decoded_extrinsic::{
+	AssetTip, DecodedExtrinsic, DigestMismatch, Era, Mortality, SignedExtensionValue,
+	SignedExtensionValues, Validity,
+};
+pub use diff::{diff_type_information, ExtrinsicMetadataDiff, MetadataDiff, VariantPath};
+#[cfg(feature = "json")]
+pub use encoder::value_from_json;
+pub use encoder::{
+	encode_call, encode_extrinsic, encode_signed_extensions, encode_value, ExtrinsicSignature,
+};
 use extrinsic_decoder::{
 	decode_extrinsic_and_collect_type_ids, decode_extrinsic_parts_and_collect_type_ids,
+	decode_extrinsic_values, decode_value_and_collect_type_ids,
 };
 use frame_metadata::RuntimeMetadata;
-use from_frame_metadata::FrameMetadataPrepared;
-use merkle_tree::MerkleTree;
-pub use merkle_tree::Proof;
+pub use from_frame_metadata::{FrameMetadataPrepared, TypeInformation};
+pub use lookup::{CallInfo, PalletInfo};
+pub use merkle_tree::{MerkleTree, Proof, TypeId};
+pub use policy::{Action, Condition, Decision, Matcher, Policy, Rule};
+pub use proof_size::ProofSizeReport;
+pub use render::{RenderContext, Renderer, Renderers};
+#[cfg(feature = "signature-verification")]
+pub use signature::{verify_signature, SignatureScheme};
 use types::MetadataDigest;
+pub use value::{Fields, Value};
 
+mod call_tree;
+mod decoded_extrinsic;
+mod diff;
+mod encoder;
+pub mod experimental;
 mod extrinsic_decoder;
 mod from_frame_metadata;
+mod lookup;
 mod merkle_tree;
+mod policy;
+mod proof_size;
+#[cfg(test)]
+mod reference_merkle_tree;
+mod render;
+#[cfg(feature = "signature-verification")]
+mod signature;
+#[cfg(test)]
+mod test_vectors;
 pub mod types;
+mod value;
 
 /// Extra information that is required to generate the [`MetadataDigest`].
 #[derive(Debug, Clone)]
@@ -52,6 +130,25 @@ pub struct ExtraInfo {
 	pub token_symbol: String,
 }
 
+impl ExtraInfo {
+	/// Build the [`MetadataDigest`] from `self` and the given hashes.
+	fn into_metadata_digest(
+		self,
+		types_tree_root: types::Hash,
+		extrinsic_metadata_hash: types::Hash,
+	) -> MetadataDigest {
+		MetadataDigest::V1 {
+			types_tree_root,
+			extrinsic_metadata_hash,
+			spec_version: self.spec_version,
+			spec_name: self.spec_name,
+			base58_prefix: self.base58_prefix,
+			decimals: self.decimals,
+			token_symbol: self.token_symbol,
+		}
+	}
+}
+
 /// Generate the [`MetadataDigest`] using the given `extra_info`.
 pub fn generate_metadata_digest(
 	metadata: &RuntimeMetadata,
@@ -61,17 +158,18 @@ pub fn generate_metadata_digest(
 
 	let type_information = prepared.as_type_information()?;
 
+	let extrinsic_metadata_hash = type_information.extrinsic_metadata.hash();
 	let tree_root = MerkleTree::new(type_information.types).root();
 
-	Ok(MetadataDigest::V1 {
-		types_tree_root: tree_root,
-		extrinsic_metadata_hash: type_information.extrinsic_metadata.hash(),
-		spec_version: extra_info.spec_version,
-		spec_name: extra_info.spec_name,
-		base58_prefix: extra_info.base58_prefix,
-		decimals: extra_info.decimals,
-		token_symbol: extra_info.token_symbol,
-	})
+	Ok(extra_info.into_metadata_digest(tree_root, extrinsic_metadata_hash))
+}
+
+/// Generate the [`TypeInformation`] for the given `metadata`.
+///
+/// This contains all the types that are part of the merkle tree and the
+/// [`ExtrinsicMetadata`](types::ExtrinsicMetadata).
+pub fn generate_type_information(metadata: &RuntimeMetadata) -> Result<TypeInformation, String> {
+	FrameMetadataPrepared::prepare(metadata)?.as_type_information()
 }
 
 /// Generate a proof for the given `extrinsic` using the given `metadata`.
@@ -95,7 +193,7 @@ pub fn generate_proof_for_extrinsic(
 	let accessed_types = decode_extrinsic_and_collect_type_ids(
 		extrinsic,
 		additional_signed,
-		&type_information,
+		&type_information.extrinsic_metadata,
 		type_information.types.values(),
 	)?;
 
@@ -121,12 +219,100 @@ pub fn verify_proof(
 	decode_extrinsic_and_collect_type_ids(
 		&mut extrinsic,
 		additional_signed,
-		&type_information,
+		&type_information.extrinsic_metadata,
 		proof.leaves.iter(),
 	)
 	.map(drop)
 }
 
+/// Verify the given `proof` against the `digest` and that it can be used to decode `extrinsic`.
+///
+/// In contrast to [`verify_proof`], this doesn't require the full metadata and is what a signer
+/// would do with a proof it received. The `extrinsic_metadata` is checked against the
+/// `extrinsic_metadata_hash` and the root calculated from the `proof` against the
+/// `types_tree_root` of the `digest`. Only then the `extrinsic` is decoded, using only the leaves
+/// of the `proof`.
+pub fn verify_proof_with_digest(
+	mut extrinsic: &[u8],
+	additional_signed: Option<&[u8]>,
+	extrinsic_metadata: &types::ExtrinsicMetadata,
+	digest: &MetadataDigest,
+	proof: &Proof,
+) -> Result<(), String> {
+	let MetadataDigest::V1 { types_tree_root, extrinsic_metadata_hash, .. } = digest else {
+		return Err("Metadata digest is disabled".into());
+	};
+
+	if extrinsic_metadata.hash() != *extrinsic_metadata_hash {
+		return Err("`extrinsic_metadata` doesn't match the digest".into());
+	}
+
+	if proof.root()? != *types_tree_root {
+		return Err("Root of the proof doesn't match the digest".into());
+	}
+
+	decode_extrinsic_and_collect_type_ids(
+		&mut extrinsic,
+		additional_signed,
+		extrinsic_metadata,
+		proof.leaves.iter(),
+	)?;
+
+	if !extrinsic.is_empty() {
+		return Err("Bytes left in `extrinsic` after decoding".into());
+	}
+
+	Ok(())
+}
+
+/// Decode the given `extrinsic` using only the leaves of `proof`.
+///
+/// Returns the decoded values of the address, the signature, the signed extensions and the call.
+/// If `additional_signed` is `Some(_)`, the values of the signed extensions in the signed data are
+/// decoded as well. The `proof` isn't checked against any digest, use
+/// [`verify_proof_with_digest`] before. [`SignedExtensionValues::check_digest`] checks that the
+/// values of the signed extensions are consistent with the digest.
+pub fn decode_extrinsic(
+	mut extrinsic: &[u8],
+	additional_signed: Option<&[u8]>,
+	extrinsic_metadata: &types::ExtrinsicMetadata,
+	proof: &Proof,
+) -> Result<DecodedExtrinsic, String> {
+	let decoded = decode_extrinsic_values(
+		&mut extrinsic,
+		additional_signed,
+		extrinsic_metadata,
+		proof.leaves.iter(),
+	)?;
+
+	if !extrinsic.is_empty() {
+		return Err("Bytes left in `extrinsic` after decoding".into());
+	}
+
+	Ok(decoded)
+}
+
+/// Decode the given `extrinsic` using only the leaves of `proof` and return its call tree.
+///
+/// The calls are returned in depth-first order, starting with the call of the extrinsic. Calls
+/// nested in other calls, e.g. the calls of `Utility.batch_all` or the call of `Proxy.proxy`, are
+/// detected by their type being the [`call_ty`](types::ExtrinsicMetadata::call_ty). Calls passed
+/// as opaque bytes are not part of the tree. The `proof` isn't checked against any digest, use
+/// [`verify_proof_with_digest`] before.
+pub fn decode_call_tree(
+	mut extrinsic: &[u8],
+	extrinsic_metadata: &types::ExtrinsicMetadata,
+	proof: &Proof,
+) -> Result<Vec<CallNode>, String> {
+	let calls = collect_call_tree(&mut extrinsic, extrinsic_metadata, proof.leaves.iter())?;
+
+	if !extrinsic.is_empty() {
+		return Err("Bytes left in `extrinsic` after decoding".into());
+	}
+
+	Ok(calls)
+}
+
 /// Data that is required for a signed extrinsic.
 pub struct SignedExtrinsicData<'a> {
 	/// The data that is directly included in the extrinsic.
@@ -167,14 +353,136 @@ pub fn generate_proof_for_extrinsic_parts(
 	MerkleTree::new(type_information.types).build_proof(accessed_types)
 }
 
+/// Generate a proof for the given `value` of the type `frame_type_id` using the given `metadata`.
+///
+/// `frame_type_id` is the id of the type in the `PortableRegistry` of the `metadata` and the type
+/// needs to be reachable from the extrinsic metadata. This can be used for values that are not
+/// directly decodable as part of an extrinsic, e.g. a `RuntimeCall` that is passed as opaque bytes
+/// to a multisig call.
+pub fn generate_proof_for_value(
+	frame_type_id: u32,
+	mut value: &[u8],
+	metadata: &RuntimeMetadata,
+) -> Result<Proof, String> {
+	let type_information = generate_type_information(metadata)?;
+
+	let type_ref = type_information.frame_type_ref(frame_type_id).ok_or_else(|| {
+		format!("Type `{frame_type_id}` is not reachable from the extrinsic metadata")
+	})?;
+
+	let value = &mut value;
+
+	let accessed_types =
+		decode_value_and_collect_type_ids(value, type_ref, type_information.types.values())?;
+
+	if !value.is_empty() {
+		return Err("Bytes left in `value` after decoding".into());
+	}
+
+	MerkleTree::new(type_information.types).build_proof(accessed_types)
+}
+
+/// Verify that the given `proof` can be used to decode `value` as `type_ref`.
+///
+/// This is the counterpart of [`generate_proof_for_value`]. The `type_ref` can be obtained using
+/// [`TypeInformation::frame_type_ref`] or from a field of another type, e.g. the
+/// [`call_ty`](types::ExtrinsicMetadata::call_ty) for a nested call. The root of the `proof`
+/// is not checked, use [`Proof::root`] to compare it against the digest.
+pub fn verify_proof_for_value(
+	mut value: &[u8],
+	type_ref: types::TypeRef,
+	proof: &Proof,
+) -> Result<(), String> {
+	decode_value_and_collect_type_ids(&mut value, type_ref, proof.leaves.iter())?;
+
+	if !value.is_empty() {
+		return Err("Bytes left in `value` after decoding".into());
+	}
+
+	Ok(())
+}
+
+/// Generate a proof for any value of the `call` of `pallet` using the given `metadata`.
+///
+/// The proofs generated by [`generate_proof_for_extrinsic`] only contain the variants of
+/// enumerations that are used by the values of the extrinsic. This proof contains every type and
+/// every variant that is reachable from the arguments of the call and can thus, be cached and
+/// reused for any extrinsic with this call. Arguments that are calls themselves, e.g. of
+/// `Utility.batch`, add the entire call enumeration.
+///
+/// If `signed` is `true`, all the types of the address, the signature and the signed extensions
+/// are included as well.
+pub fn generate_proof_for_call_template(
+	pallet: &str,
+	call: &str,
+	signed: bool,
+	metadata: &RuntimeMetadata,
+) -> Result<Proof, String> {
+	let type_information = generate_type_information(metadata)?;
+	let extrinsic_metadata = &type_information.extrinsic_metadata;
+
+	let mut type_ids = BTreeSet::new();
+	type_information.collect_call_template_types(pallet, call, &mut type_ids)?;
+
+	if signed {
+		type_information.collect_all_types(&extrinsic_metadata.address_ty, &mut type_ids);
+		type_information.collect_all_types(&extrinsic_metadata.signature_ty, &mut type_ids);
+
+		for se in &extrinsic_metadata.signed_extensions {
+			type_information.collect_all_types(&se.included_in_extrinsic, &mut type_ids);
+			type_information.collect_all_types(&se.included_in_signed_data, &mut type_ids);
+		}
+	}
+
+	MerkleTree::new(type_information.types).build_proof(type_ids)
+}
+
+/// Generate a proof that contains the given `type_ids` using the given `metadata`.
+///
+/// The `type_ids` are the keys of [`TypeInformation::types`].
+pub fn generate_proof_for_type_ids(
+	type_ids: impl IntoIterator<Item = TypeId>,
+	metadata: &RuntimeMetadata,
+) -> Result<Proof, String> {
+	let type_information = generate_type_information(metadata)?;
+
+	MerkleTree::new(type_information.types).build_proof(type_ids)
+}
+
+/// Generate the differences between the `old` and the `new` metadata.
+///
+/// See [`diff_type_information`] for more information.
+pub fn generate_metadata_diff(
+	old: &RuntimeMetadata,
+	new: &RuntimeMetadata,
+) -> Result<MetadataDiff, String> {
+	Ok(diff_type_information(&generate_type_information(old)?, &generate_type_information(new)?))
+}
+
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
 	use super::*;
 	use ::frame_metadata::RuntimeMetadataPrefixed;
-	use codec::Decode;
+	use codec::{Compact, Decode, Encode};
 	use std::fs;
 
-	const FIXTURES: &[(&str, &str)] = &[
+	/// Load the metadata of the given `fixture`.
+	pub(crate) fn load_metadata(fixture: &str) -> RuntimeMetadata {
+		let metadata = String::from_utf8(
+			fs::read(format!("{}/fixtures/{fixture}", env!("CARGO_MANIFEST_DIR"))).unwrap(),
+		)
+		.unwrap();
+
+		let metadata = Option::<Vec<u8>>::decode(
+			&mut &array_bytes::hex2bytes(metadata.strip_suffix("\n").unwrap()).unwrap()[..],
+		)
+		.unwrap()
+		.unwrap();
+
+		RuntimeMetadataPrefixed::decode(&mut &metadata[..]).unwrap().1
+	}
+
+	pub(crate) const FIXTURES: &[(&str, &str)] = &[
 		(
 			"rococo_metadata_v15",
 			"0x6619a31025a9a14086a34da4ede7ed61258b9f55c12baae8bc801317869d2dfb",
@@ -201,6 +509,17 @@ mod tests {
 		),
 	];
 
+	/// Returns the index of the `pallet` and the index of its `call`.
+	pub(crate) fn call_index(
+		type_information: &TypeInformation,
+		pallet: &str,
+		call: &str,
+	) -> (u8, u8) {
+		let call = type_information.lookup_call(pallet, call).unwrap();
+
+		(call.pallet_index, call.call_index)
+	}
+
 	#[test]
 	fn calculate_metadata_digest_works() {
 		let extra_info = ExtraInfo {
@@ -243,7 +562,7 @@ mod tests {
 							p.type_def
 								.as_enumeration()
 								.and_then(|p| v.type_def.as_enumeration().map(|v| (p, v)))
-								.map_or(false, |(p, v)| p.index.0 < v.index.0)
+								.is_some_and(|(p, v)| p.index.0 < v.index.0)
 						{
 							Some(v.clone())
 						} else {
@@ -254,4 +573,404 @@ mod tests {
 				.unwrap();
 		}
 	}
+
+	#[test]
+	fn verify_proof_with_digest_works() {
+		use merkle_tree::tests::{TEST_ADDITIONAL_SIGNED, TEST_EXT};
+
+		let metadata = load_metadata("rococo_metadata_v15");
+		let extrinsic = array_bytes::hex2bytes(TEST_EXT).unwrap();
+		let additional_signed = array_bytes::hex2bytes(TEST_ADDITIONAL_SIGNED).unwrap();
+		let extrinsic_metadata = generate_type_information(&metadata).unwrap().extrinsic_metadata;
+		let digest = generate_metadata_digest(
+			&metadata,
+			ExtraInfo {
+				spec_version: 1,
+				spec_name: "nice".into(),
+				base58_prefix: 1,
+				decimals: 1,
+				token_symbol: "lol".into(),
+			},
+		)
+		.unwrap();
+
+		let proof =
+			generate_proof_for_extrinsic(&extrinsic, Some(&additional_signed), &metadata).unwrap();
+
+		let verify = |extrinsic: &[u8], extrinsic_metadata, digest, proof| {
+			verify_proof_with_digest(
+				extrinsic,
+				Some(&additional_signed),
+				extrinsic_metadata,
+				digest,
+				proof,
+			)
+		};
+
+		verify(&extrinsic, &extrinsic_metadata, &digest, &proof).unwrap();
+
+		// Bytes left after decoding.
+		let mut too_long = extrinsic.clone();
+		too_long.push(0);
+		assert!(verify(&too_long, &extrinsic_metadata, &digest, &proof).is_err());
+
+		// Different extrinsic metadata.
+		let mut wrong_extrinsic_metadata = extrinsic_metadata.clone();
+		wrong_extrinsic_metadata.signed_extensions.pop();
+		assert!(verify(&extrinsic, &wrong_extrinsic_metadata, &digest, &proof).is_err());
+
+		// Different types root.
+		let mut wrong_digest = digest.clone();
+		let MetadataDigest::V1 { types_tree_root, .. } = &mut wrong_digest else {
+			panic!("Digest is enabled")
+		};
+		types_tree_root[0] ^= 1;
+		assert!(verify(&extrinsic, &extrinsic_metadata, &wrong_digest, &proof).is_err());
+
+		assert!(verify(&extrinsic, &extrinsic_metadata, &MetadataDigest::Disabled, &proof).is_err());
+
+		// Modified leaf.
+		let mut wrong_proof = proof.clone();
+		wrong_proof.leaves[0].path.push("Modified".into());
+		assert!(verify(&extrinsic, &extrinsic_metadata, &digest, &wrong_proof).is_err());
+
+		// Missing leaf, with the node hash of the leaf.
+		let mut missing_leaf = proof.clone();
+		missing_leaf.leaves.pop();
+		missing_leaf.leaf_indices.pop();
+		assert!(verify(&extrinsic, &extrinsic_metadata, &digest, &missing_leaf).is_err());
+	}
+
+	#[test]
+	fn decode_extrinsic_works() {
+		use merkle_tree::tests::{TEST_ADDITIONAL_SIGNED, TEST_EXT};
+
+		let metadata = load_metadata("rococo_metadata_v15");
+		let extrinsic = array_bytes::hex2bytes(TEST_EXT).unwrap();
+		let additional_signed = array_bytes::hex2bytes(TEST_ADDITIONAL_SIGNED).unwrap();
+		let extrinsic_metadata = generate_type_information(&metadata).unwrap().extrinsic_metadata;
+		let proof =
+			generate_proof_for_extrinsic(&extrinsic, Some(&additional_signed), &metadata).unwrap();
+
+		let decoded =
+			decode_extrinsic(&extrinsic, Some(&additional_signed), &extrinsic_metadata, &proof)
+				.unwrap();
+		let signed_extensions = &decoded.signed_extensions;
+		assert_eq!(Some(0), signed_extensions.nonce());
+		assert_eq!(Some(0), signed_extensions.tip());
+		assert_eq!(None, signed_extensions.asset_tip());
+		assert_eq!(
+			Some(Mortality { era: Era::Mortal(7, 0), birth_block_hash: Some([0; 32]) }),
+			signed_extensions.mortality()
+		);
+		assert_eq!(Some([0; 32]), signed_extensions.genesis_hash());
+		assert_eq!(
+			Ok(Validity::Mortal {
+				period: 256,
+				phase: 0,
+				birth_block: Some(256),
+				death_block: Some(512),
+			}),
+			Era::Mortal(7, 0).validity(Some(300))
+		);
+
+		// The era can also be decoded using only the leaves of the proof.
+		assert_eq!(Ok(Era::Mortal(7, 0)), Era::decode_with_proof(&[7, 0], &proof));
+		assert!(Era::decode_with_proof(&[7], &proof).is_err());
+		assert!(Era::decode_with_proof(&[0], &proof).is_err());
+		assert_eq!(
+			Some(&Value::Composite(Vec::new())),
+			signed_extensions.get("CheckWeight").unwrap().included_in_extrinsic.as_ref()
+		);
+		assert_eq!(8, signed_extensions.iter().count());
+
+		// The spec version in `TEST_ADDITIONAL_SIGNED` doesn't match the one of the digest.
+		let digest = generate_metadata_digest(
+			&metadata,
+			ExtraInfo {
+				spec_version: 1,
+				spec_name: "nice".into(),
+				base58_prefix: 1,
+				decimals: 1,
+				token_symbol: "lol".into(),
+			},
+		)
+		.unwrap();
+		assert_eq!(Some(1006002), signed_extensions.spec_version());
+		assert_eq!(Some(24), signed_extensions.transaction_version());
+		assert_eq!(
+			Err(vec![DigestMismatch::SpecVersion { expected: 1, found: 1006002 }]),
+			signed_extensions.check_digest(&digest)
+		);
+		let digest = generate_metadata_digest(
+			&metadata,
+			ExtraInfo {
+				spec_version: 1006002,
+				spec_name: "rococo".into(),
+				base58_prefix: 42,
+				decimals: 12,
+				token_symbol: "ROC".into(),
+			},
+		)
+		.unwrap();
+		assert_eq!(Ok(()), signed_extensions.check_digest(&digest));
+
+		assert_eq!(Some("Id"), decoded.address.as_ref().and_then(Value::variant_name));
+		assert_eq!(Some("Sr25519"), decoded.signature.as_ref().and_then(Value::variant_name));
+		assert_eq!(Some("Balances"), decoded.call.variant_name());
+		let call = &decoded.call.fields().unwrap()[0].1;
+		assert_eq!(Some("transfer_keep_alive"), call.variant_name());
+		assert_eq!(Some(10), call.field("value").and_then(Value::as_unsigned));
+
+		// Without the additional signed data only the values in the extrinsic are available.
+		let decoded = decode_extrinsic(&extrinsic, None, &extrinsic_metadata, &proof).unwrap();
+		assert_eq!(None, decoded.signing_payload());
+		assert_eq!(Some(0), decoded.signed_extensions.nonce());
+		assert_eq!(None, decoded.signed_extensions.genesis_hash());
+		assert_eq!(
+			Some(Mortality { era: Era::Mortal(7, 0), birth_block_hash: None }),
+			decoded.signed_extensions.mortality()
+		);
+
+		let mut too_long = extrinsic.clone();
+		too_long.push(0);
+		assert!(decode_extrinsic(&too_long, None, &extrinsic_metadata, &proof).is_err());
+		let mut too_long = additional_signed.clone();
+		too_long.push(0);
+		assert_eq!(
+			Err("Bytes left in `additional_signed` after decoding".into()),
+			decode_extrinsic(&extrinsic, Some(&too_long), &extrinsic_metadata, &proof)
+		);
+
+		// An unsigned extrinsic has no signed extensions.
+		let call = array_bytes::hex2bytes(merkle_tree::tests::TEST_CALL).unwrap();
+		let unsigned = [&[4u8][..], &call].concat().encode();
+		let proof = generate_proof_for_extrinsic(&unsigned, None, &metadata).unwrap();
+		let decoded = decode_extrinsic(&unsigned, None, &extrinsic_metadata, &proof).unwrap();
+		assert_eq!(None, decoded.address);
+		assert_eq!(None, decoded.signature);
+		assert_eq!(None, decoded.signed_extensions.nonce());
+		assert_eq!(0, decoded.signed_extensions.iter().count());
+
+		// The proof doesn't contain the types of a signed extrinsic.
+		assert!(decode_extrinsic(&extrinsic, None, &extrinsic_metadata, &proof).is_err());
+	}
+
+	#[test]
+	fn decode_call_tree_works() {
+		use merkle_tree::tests::{TEST_ADDITIONAL_SIGNED, TEST_EXT};
+
+		let metadata = load_metadata("rococo_metadata_v15");
+		let type_information = generate_type_information(&metadata).unwrap();
+		let extrinsic_metadata = &type_information.extrinsic_metadata;
+
+		let call_index = |pallet, call| call_index(&type_information, pallet, call);
+
+		// `Utility.batch_all([Proxy.proxy(Balances.transfer_keep_alive), System.remark])`
+		let transfer =
+			(call_index("Balances", "transfer_keep_alive"), 0u8, [1u8; 32], Compact(10u128))
+				.encode();
+		let proxy =
+			[(call_index("Proxy", "proxy"), 0u8, [2u8; 32], None::<u8>).encode(), transfer.clone()]
+				.concat();
+		let remark = (call_index("System", "remark"), b"RFC78".to_vec()).encode();
+		let batch = [
+			call_index("Utility", "batch_all").encode(),
+			Compact(2u32).encode(),
+			proxy.clone(),
+			remark.clone(),
+		]
+		.concat();
+		let extrinsic = [&[4u8][..], &batch].concat().encode();
+
+		let proof = generate_proof_for_extrinsic(&extrinsic, None, &metadata).unwrap();
+		let calls = decode_call_tree(&extrinsic, extrinsic_metadata, &proof).unwrap();
+
+		let node = |pallet: &str, call: &str, start: usize, len: usize, depth: usize| CallNode {
+			pallet: pallet.into(),
+			call: call.into(),
+			range: start..start + len,
+			depth,
+		};
+		let batch_start = extrinsic.len() - batch.len();
+		let proxy_start = batch_start + 3;
+		let remark_start = proxy_start + proxy.len();
+		assert_eq!(
+			vec![
+				node("Utility", "batch_all", batch_start, batch.len(), 0),
+				node("Proxy", "proxy", proxy_start, proxy.len(), 1),
+				node(
+					"Balances",
+					"transfer_keep_alive",
+					remark_start - transfer.len(),
+					transfer.len(),
+					2
+				),
+				node("System", "remark", remark_start, remark.len(), 1),
+			],
+			calls
+		);
+		assert_eq!(extrinsic.len(), calls[0].range.end);
+
+		// The call of a signed extrinsic.
+		let extrinsic = array_bytes::hex2bytes(TEST_EXT).unwrap();
+		let additional_signed = array_bytes::hex2bytes(TEST_ADDITIONAL_SIGNED).unwrap();
+		let proof =
+			generate_proof_for_extrinsic(&extrinsic, Some(&additional_signed), &metadata).unwrap();
+		let calls = decode_call_tree(&extrinsic, extrinsic_metadata, &proof).unwrap();
+		assert_eq!(1, calls.len());
+		assert_eq!(("Balances", "transfer_keep_alive"), (&calls[0].pallet[..], &calls[0].call[..]));
+		assert_eq!(extrinsic.len(), calls[0].range.end);
+
+		let mut too_long = extrinsic.clone();
+		too_long.push(0);
+		assert!(decode_call_tree(&too_long, extrinsic_metadata, &proof).is_err());
+	}
+
+	#[test]
+	fn generate_proof_for_call_template_works() {
+		use merkle_tree::tests::{TEST_ADDITIONAL_SIGNED, TEST_EXT};
+
+		let metadata = load_metadata("rococo_metadata_v15");
+		let type_information = generate_type_information(&metadata).unwrap();
+		let extrinsic_metadata = &type_information.extrinsic_metadata;
+		let call_index = call_index(&type_information, "Balances", "transfer_keep_alive");
+
+		// `MultiAddress::Id` and `MultiAddress::Address20`.
+		let with_id = [&[4u8][..], &(call_index, 0u8, [1u8; 32], Compact(10u128)).encode()]
+			.concat()
+			.encode();
+		let with_address20 = [&[4u8][..], &(call_index, 4u8, [1u8; 20], Compact(10u128)).encode()]
+			.concat()
+			.encode();
+
+		let template =
+			generate_proof_for_call_template("Balances", "transfer_keep_alive", false, &metadata)
+				.unwrap();
+		for extrinsic in [&with_id, &with_address20] {
+			verify_proof(extrinsic, None, &metadata, &template).unwrap();
+			decode_extrinsic(extrinsic, None, extrinsic_metadata, &template).unwrap();
+		}
+
+		// The proof for an extrinsic only contains the variant that is used.
+		let proof = generate_proof_for_extrinsic(&with_id, None, &metadata).unwrap();
+		assert!(proof.leaves.len() < template.leaves.len());
+		assert!(verify_proof(&with_address20, None, &metadata, &proof).is_err());
+
+		// The types of signed extrinsics are only included when requested.
+		let extrinsic = array_bytes::hex2bytes(TEST_EXT).unwrap();
+		let additional_signed = array_bytes::hex2bytes(TEST_ADDITIONAL_SIGNED).unwrap();
+		assert!(verify_proof(&extrinsic, Some(&additional_signed), &metadata, &template).is_err());
+		let template =
+			generate_proof_for_call_template("Balances", "transfer_keep_alive", true, &metadata)
+				.unwrap();
+		verify_proof(&extrinsic, Some(&additional_signed), &metadata, &template).unwrap();
+
+		assert!(generate_proof_for_call_template("Balances", "unknown", false, &metadata).is_err());
+		assert!(generate_proof_for_call_template("Unknown", "remark", false, &metadata).is_err());
+	}
+
+	#[test]
+	fn proof_for_value_works() {
+		use merkle_tree::tests::TEST_CALL;
+
+		let metadata = load_metadata("rococo_metadata_v15");
+		let RuntimeMetadata::V15(frame_metadata) = &metadata else { panic!("Fixture is V15") };
+		let type_information = generate_type_information(&metadata).unwrap();
+		let call = array_bytes::hex2bytes(TEST_CALL).unwrap();
+		let call_ty = frame_metadata.extrinsic.call_ty.id;
+
+		assert_eq!(
+			Some(type_information.extrinsic_metadata.call_ty),
+			type_information.frame_type_ref(call_ty)
+		);
+
+		// A call is decoded the same way as the call of an extrinsic.
+		let proof = generate_proof_for_value(call_ty, &call, &metadata).unwrap();
+		assert_eq!(generate_proof_for_extrinsic_parts(&call, None, &metadata).unwrap(), proof);
+		verify_proof_for_value(&call, type_information.extrinsic_metadata.call_ty, &proof).unwrap();
+
+		let mut too_long = call.clone();
+		too_long.push(0);
+		assert!(generate_proof_for_value(call_ty, &too_long, &metadata).is_err());
+		assert!(verify_proof_for_value(
+			&too_long,
+			type_information.extrinsic_metadata.call_ty,
+			&proof
+		)
+		.is_err());
+
+		// Signed extension payloads.
+		let signed_extension = |identifier: &str| {
+			frame_metadata
+				.extrinsic
+				.signed_extensions
+				.iter()
+				.find(|se| se.identifier == identifier)
+				.unwrap()
+		};
+
+		let root = MerkleTree::new(type_information.types.clone()).root();
+
+		// `CheckNonce(Compact<u32>)`
+		let nonce_ty = signed_extension("CheckNonce").ty.id;
+		let nonce = Compact(5u32).encode();
+		let proof = generate_proof_for_value(nonce_ty, &nonce, &metadata).unwrap();
+		assert_eq!(1, proof.leaves.len());
+		assert_eq!(root, proof.root().unwrap());
+		let nonce_ty = type_information.frame_type_ref(nonce_ty).unwrap();
+		verify_proof_for_value(&nonce, nonce_ty, &proof).unwrap();
+
+		// The spec version is a primitive and thus, not part of the merkle tree.
+		let spec_version_ty = signed_extension("CheckSpecVersion").additional_signed.id;
+		let spec_version = 1u32.encode();
+		let proof = generate_proof_for_value(spec_version_ty, &spec_version, &metadata).unwrap();
+		assert_eq!(Some(types::TypeRef::U32), type_information.frame_type_ref(spec_version_ty));
+		assert!(proof.leaves.is_empty());
+		assert_eq!(root, proof.root().unwrap());
+		verify_proof_for_value(&spec_version, types::TypeRef::U32, &proof).unwrap();
+
+		// Not reachable from the extrinsic metadata.
+		assert!(generate_proof_for_value(frame_metadata.ty.id, &[], &metadata).is_err());
+		assert!(generate_proof_for_value(u32::MAX, &[], &metadata).is_err());
+	}
+
+	#[test]
+	fn frame_type_id_mapping_works() {
+		for (fixture, _) in FIXTURES {
+			let metadata = load_metadata(fixture);
+			let RuntimeMetadata::V15(frame_metadata) = &metadata else { panic!("Fixture is V15") };
+			let type_information = generate_type_information(&metadata).unwrap();
+
+			for ty in type_information.types.values() {
+				let frame_type_id = type_information.frame_type_id(ty.type_id.0).unwrap();
+
+				assert_eq!(
+					Some(types::TypeRef::ById(ty.type_id)),
+					type_information.frame_type_ref(frame_type_id)
+				);
+
+				let frame_type = &frame_metadata.types.types[frame_type_id as usize].ty;
+				assert_eq!(
+					ty.path,
+					frame_type.path.segments.iter().map(String::as_str).collect::<Vec<_>>()
+				);
+				assert!(type_information
+					.type_ids_by_path(&ty.path.iter().map(String::as_str).collect::<Vec<_>>())
+					.any(|ids| ids == (frame_type_id, ty.type_id.0)));
+			}
+
+			let call_ty = frame_metadata.extrinsic.call_ty.id;
+			let call_path = frame_metadata.types.types[call_ty as usize].ty.path.segments.clone();
+			assert_eq!(
+				vec![(call_ty, type_information.extrinsic_metadata.call_ty.id().unwrap())],
+				type_information
+					.type_ids_by_path(&call_path.iter().map(String::as_str).collect::<Vec<_>>())
+					.collect::<Vec<_>>(),
+			);
+
+			assert_eq!(None, type_information.frame_type_id(u32::MAX));
+			assert_eq!(None, type_information.type_ids_by_path(&["Unknown"]).next());
+		}
+	}
 }
@@ -0,0 +1,196 @@
+//! Lookup of pallets and calls by their name.
+//!
+//! The [`call_ty`](crate::types::ExtrinsicMetadata::call_ty) is an enumeration with one variant per
+//! pallet. The only field of every variant is the enumeration of the calls of the pallet, with one
+//! variant per call. The fields of these variants are the arguments of the calls.
+
+use alloc::{collections::BTreeSet, format, string::String, vec::Vec};
+
+use crate::{
+	from_frame_metadata::TypeInformation,
+	merkle_tree::TypeId,
+	types::{EnumerationVariant, Field},
+};
+
+/// A call of a pallet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallInfo<'a> {
+	/// The name of the pallet.
+	pub pallet: &'a str,
+	/// The index of the pallet, the first byte of an encoded call.
+	pub pallet_index: u8,
+	/// The name of the call.
+	pub call: &'a str,
+	/// The index of the call, the second byte of an encoded call.
+	pub call_index: u8,
+	/// The arguments of the call.
+	pub args: &'a [Field],
+}
+
+/// A pallet that has calls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PalletInfo<'a> {
+	/// The name of the pallet.
+	pub name: &'a str,
+	/// The index of the pallet.
+	pub index: u8,
+	/// The calls of the pallet, ordered by their index.
+	pub calls: Vec<CallInfo<'a>>,
+	/// The number of leaves that are required for decoding any call of the pallet.
+	///
+	/// Nested calls are not followed, as they would add the entire call enumeration.
+	pub leaf_count: usize,
+}
+
+impl TypeInformation {
+	/// Returns all the pallets that have calls, ordered by their index.
+	pub fn pallets(&self) -> Vec<PalletInfo<'_>> {
+		self.pallet_variants()
+			.map(|(_, pallet, pallet_call_ty)| self.pallet_info(pallet, pallet_call_ty))
+			.collect()
+	}
+
+	/// Returns the pallet with the given `name`.
+	pub fn lookup_pallet(&self, name: &str) -> Option<PalletInfo<'_>> {
+		self.pallet_variants()
+			.find(|(_, pallet, _)| pallet.name == name)
+			.map(|(_, pallet, pallet_call_ty)| self.pallet_info(pallet, pallet_call_ty))
+	}
+
+	/// Returns the `call` of the given `pallet`.
+	///
+	/// Returns `None` if either the pallet or the call doesn't exist.
+	pub fn lookup_call(&self, pallet: &str, call: &str) -> Option<CallInfo<'_>> {
+		let (_, pallet, pallet_call_ty) =
+			self.pallet_variants().find(|(_, p, _)| p.name == pallet)?;
+
+		self.call_variants(pallet_call_ty)
+			.find(|(_, c)| c.name == call)
+			.map(|(_, call)| call_info(pallet, call))
+	}
+
+	/// Collect all the types that are required for decoding any value of the `call` of `pallet`.
+	///
+	/// In contrast to decoding a value, this includes every variant of the enumerations that are
+	/// reachable from the arguments of the call. Arguments that are calls themselves add the
+	/// entire call enumeration.
+	pub(crate) fn collect_call_template_types(
+		&self,
+		pallet: &str,
+		call: &str,
+		collected: &mut BTreeSet<TypeId>,
+	) -> Result<(), String> {
+		let (pallet_id, pallet, pallet_call_ty) = self
+			.pallet_variants()
+			.find(|(_, p, _)| p.name == pallet)
+			.ok_or_else(|| format!("Unknown pallet `{pallet}`"))?;
+		let (call_id, call) = self
+			.call_variants(pallet_call_ty)
+			.find(|(_, c)| c.name == call)
+			.ok_or_else(|| format!("Unknown call `{call}` of pallet `{}`", pallet.name))?;
+
+		collected.insert(*pallet_id);
+		collected.insert(*call_id);
+		call.fields.iter().for_each(|f| self.collect_all_types(&f.ty, collected));
+
+		Ok(())
+	}
+
+	/// Returns the variants of the `call_ty` with their [`TypeId`] and the id of the enumeration
+	/// of their calls.
+	fn pallet_variants(&self) -> impl Iterator<Item = (&TypeId, &EnumerationVariant, u32)> {
+		let call_ty = self.extrinsic_metadata.call_ty.id();
+
+		call_ty.into_iter().flat_map(move |call_ty| {
+			self.types_by_id(call_ty).filter_map(|(id, ty)| {
+				let pallet = ty.type_def.as_enumeration()?;
+				let pallet_call_ty = pallet.fields.first()?.ty.id()?;
+
+				Some((id, pallet, pallet_call_ty))
+			})
+		})
+	}
+
+	/// Returns the variants of the enumeration of the calls of a pallet with their [`TypeId`].
+	fn call_variants(
+		&self,
+		pallet_call_ty: u32,
+	) -> impl Iterator<Item = (&TypeId, &EnumerationVariant)> {
+		self.types_by_id(pallet_call_ty)
+			.filter_map(|(id, ty)| Some((id, ty.type_def.as_enumeration()?)))
+	}
+
+	fn pallet_info<'a>(
+		&'a self,
+		pallet: &'a EnumerationVariant,
+		pallet_call_ty: u32,
+	) -> PalletInfo<'a> {
+		let call_ty = self.extrinsic_metadata.call_ty.id();
+
+		let mut visited = BTreeSet::new();
+		for field in &pallet.fields {
+			self.visit_all_types(&field.ty, &mut |id| {
+				Some(id.type_id()) != call_ty && visited.insert(id)
+			});
+		}
+
+		PalletInfo {
+			name: &pallet.name,
+			index: pallet.index.0 as u8,
+			calls: self
+				.call_variants(pallet_call_ty)
+				.map(|(_, call)| call_info(pallet, call))
+				.collect(),
+			// The leaf of the pallet variant itself is not part of `visited`.
+			leaf_count: 1 + visited.len(),
+		}
+	}
+}
+
+fn call_info<'a>(pallet: &'a EnumerationVariant, call: &'a EnumerationVariant) -> CallInfo<'a> {
+	CallInfo {
+		pallet: &pallet.name,
+		pallet_index: pallet.index.0 as u8,
+		call: &call.name,
+		call_index: call.index.0 as u8,
+		args: &call.fields,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{generate_type_information, tests::load_metadata, types::TypeRef};
+
+	#[test]
+	fn lookup_works() {
+		let metadata = load_metadata("rococo_metadata_v15");
+		let type_information = generate_type_information(&metadata).unwrap();
+
+		let transfer = type_information.lookup_call("Balances", "transfer_keep_alive").unwrap();
+		assert_eq!(("Balances", "transfer_keep_alive"), (transfer.pallet, transfer.call));
+		assert_eq!(
+			[Some("dest"), Some("value")],
+			transfer.args.iter().map(|f| f.name.as_deref()).collect::<Vec<_>>()[..]
+		);
+		assert_eq!(TypeRef::CompactU128, transfer.args[1].ty);
+		assert_eq!((4, 3), (transfer.pallet_index, transfer.call_index));
+
+		assert!(type_information.lookup_call("Balances", "unknown").is_none());
+		assert!(type_information.lookup_call("Unknown", "transfer_keep_alive").is_none());
+
+		let pallets = type_information.pallets();
+		assert!(pallets.windows(2).all(|p| p[0].index < p[1].index));
+		assert!(pallets.iter().all(|p| !p.calls.is_empty() && p.leaf_count > p.calls.len()));
+
+		let balances = type_information.lookup_pallet("Balances").unwrap();
+		assert_eq!(Some(&balances), pallets.iter().find(|p| p.name == "Balances"));
+		assert_eq!(transfer.pallet_index, balances.index);
+		assert!(balances.calls.contains(&transfer));
+		assert!(balances.calls.windows(2).all(|c| c[0].call_index < c[1].call_index));
+
+		// Nested calls are not counted, `Utility` would otherwise contain every pallet.
+		let utility = type_information.lookup_pallet("Utility").unwrap();
+		assert!(utility.leaf_count < type_information.types.len() / 2);
+	}
+}
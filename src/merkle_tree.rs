@@ -1,29 +1,16 @@
 use crate::types::{Hash, Type};
-use alloc::{
-	collections::{BTreeMap, VecDeque},
-	format,
-	string::String,
-	vec::Vec,
-};
-use codec::{Compact, Encode};
+use alloc::{format, string::String, vec, vec::Vec};
+use codec::Encode;
 use core::{cmp::Ordering, fmt::Debug, iter::Peekable};
 
-/// A node of a [`MerkleTree`].
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
-pub enum MerkleTreeNode {
-	Node { left: Hash, right: Hash },
-	Leaf { leaf_index: Compact<u32>, ty: Type },
-}
-
-impl MerkleTreeNode {
-	fn hash(&self) -> Hash {
-		match self {
-			Self::Node { left, right } => blake3::hash(&(left, right).encode()).into(),
-			Self::Leaf { ty, .. } => blake3::hash(&ty.encode()).into(),
-		}
-	}
+/// Calculate the hash of an inner node.
+fn hash_node(left: &Hash, right: &Hash) -> Hash {
+	blake3::hash(&(left, right).encode()).into()
 }
 
+/// The identifier of a leaf in the merkle tree.
+///
+/// Each variant of an enumeration is a separate leaf.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum TypeId {
 	Enumeration { type_id: u32, variant: u32 },
@@ -157,15 +144,94 @@ pub struct Proof {
 	pub nodes: Vec<Hash>,
 }
 
+impl Proof {
+	/// Calculate the root hash of the merkle tree this proof belongs to.
+	///
+	/// Returns an error if the proof is malformed, e.g. the leaves are not sorted, there are not
+	/// enough node hashes or there are leaves or node hashes that are not used. A proof without
+	/// any leaves and node hashes is the proof of the empty tree and returns `[0; 32]`. The
+	/// calculation never panics and the work is bounded by the size of the proof, so it is safe to
+	/// call it on untrusted proofs.
+	pub fn root(&self) -> Result<Hash, String> {
+		if self.leaves.len() != self.leaf_indices.len() {
+			return Err(format!(
+				"Number of leaves ({}) and leaf indices ({}) differ",
+				self.leaves.len(),
+				self.leaf_indices.len()
+			));
+		}
+
+		// The proof for an empty tree.
+		if self.leaves.is_empty() && self.nodes.is_empty() {
+			return Ok(Hash::default());
+		}
+
+		let mut leaves = self.leaves.iter().zip(&self.leaf_indices).peekable();
+		let mut nodes = self.nodes.iter();
+
+		let root = Self::calculate_hash(0, &mut leaves, &mut nodes)?;
+
+		if leaves.next().is_some() {
+			return Err("Leaves are not sorted, not unique or are not leaves".into());
+		}
+
+		if nodes.next().is_some() {
+			return Err("Not all node hashes are used".into());
+		}
+
+		Ok(root)
+	}
+
+	/// Calculate the hash of the node at `node_index`.
+	///
+	/// The node indices are `u64` to not overflow on 32bit platforms. As the leaf indices are
+	/// `u32`, only nodes up to level `32` are visited.
+	fn calculate_hash<'a>(
+		node_index: u64,
+		leaves: &mut Peekable<impl Iterator<Item = (&'a Type, &'a u32)>>,
+		nodes: &mut impl Iterator<Item = &'a Hash>,
+	) -> Result<Hash, String> {
+		if let Some((leaf, leaf_index)) = leaves.peek() {
+			let leaf_index = u64::from(**leaf_index);
+
+			if leaf_index == node_index {
+				let hash = blake3::hash(&leaf.encode()).into();
+				leaves.next();
+				return Ok(hash);
+			}
+
+			let node_level = (node_index + 1).ilog2();
+			let leaf_level = (leaf_index + 1).ilog2();
+
+			// Check if applying X times the parent function to the leaf leads to
+			// `node_index`. X is the level difference.
+			if leaf_level > node_level &&
+				(leaf_index + 1) >> (leaf_level - node_level) == node_index + 1
+			{
+				let left = Self::calculate_hash(node_index * 2 + 1, leaves, nodes)?;
+				let right = Self::calculate_hash(node_index * 2 + 2, leaves, nodes)?;
+
+				return Ok(hash_node(&left, &right));
+			}
+		}
+
+		nodes.next().copied().ok_or_else(|| "Not enough node hashes".into())
+	}
+}
+
 /// Merkle tree used to calculate the root hash of the metadata.
 ///
 /// The internal representation is a complete binary tree with all the
-/// leaves being the type ids.
+/// leaves being the type ids. The tree is stored as a flat array in heap order, so the children of
+/// the node at index `i` are at `2 * i + 1` and `2 * i + 2`. The inner nodes come first, followed
+/// by the leaves.
 pub struct MerkleTree {
-	root_hash: Hash,
-	nodes: BTreeMap<Hash, MerkleTreeNode>,
-	type_id_to_leaf_index: BTreeMap<TypeId, usize>,
-	node_index_to_hash: BTreeMap<NodeIndex, Hash>,
+	/// The hashes of all nodes, indexed by [`NodeIndex`].
+	pub(crate) hashes: Vec<Hash>,
+	/// The leaves in order.
+	leaves: Vec<Type>,
+	/// The type ids of the leaves with the index of the leaf, sorted by the type id.
+	type_ids: Vec<(TypeId, u32)>,
 }
 
 impl MerkleTree {
@@ -173,109 +239,229 @@ impl MerkleTree {
 	///
 	/// The `leaves` are inserted in order.
 	pub fn new(leaves: impl IntoIterator<Item = (TypeId, Type)>) -> Self {
-		let mut nodes = BTreeMap::default();
-
-		let mut type_id_to_leaf_index = BTreeMap::<TypeId, usize>::default();
-
-		let mut intermediate_nodes = leaves
+		let mut type_ids = Vec::new();
+		let leaves = leaves
 			.into_iter()
 			.enumerate()
 			.map(|(leaf_index, (type_id, ty))| {
-				let element = MerkleTreeNode::Leaf { ty, leaf_index: (leaf_index as u32).into() };
+				type_ids.push((type_id, leaf_index as u32));
+				ty
+			})
+			.collect::<Vec<_>>();
 
-				let hash = element.hash();
-				type_id_to_leaf_index.insert(type_id, leaf_index);
+		type_ids.sort_by_key(|(type_id, _)| *type_id);
 
-				nodes.insert(hash, element);
+		let hash_leaf = |ty: &Type| blake3::hash(&ty.encode()).into();
 
-				hash
-			})
-			.collect::<VecDeque<_>>();
+		#[cfg(feature = "parallel")]
+		let leaf_hashes = {
+			use rayon::prelude::*;
+
+			leaves.par_iter().map(hash_leaf).collect()
+		};
+		#[cfg(not(feature = "parallel"))]
+		let leaf_hashes = leaves.iter().map(hash_leaf).collect();
+
+		Self { hashes: Self::calculate_hashes(leaf_hashes), leaves, type_ids }
+	}
+
+	/// Calculate the hashes of all nodes using the given `leaf_hashes`.
+	fn calculate_hashes(leaf_hashes: Vec<Hash>) -> Vec<Hash> {
+		let num_inner_nodes = leaf_hashes.len().saturating_sub(1);
 
-		let mut hashes = VecDeque::<Hash>::default();
+		let mut hashes = vec![Hash::default(); num_inner_nodes];
+		hashes.extend(leaf_hashes);
 
-		while intermediate_nodes.len() > 1 {
-			let right = intermediate_nodes.pop_back().expect("We have more than one element; qed");
-			hashes.push_front(right);
-			let left = intermediate_nodes.pop_back().expect("We have more than one element; qed");
-			hashes.push_front(left);
+		// Calculate the inner nodes level by level, starting with the deepest level. The children
+		// of a level are all stored after the level, so each level only depends on already
+		// calculated hashes.
+		let mut level_end = num_inner_nodes;
+		while level_end > 0 {
+			let level_start = (1 << level_end.ilog2()) - 1;
+			let (parents, children) = hashes.split_at_mut(level_end);
 
-			let element = MerkleTreeNode::Node { left, right };
-			let hash = element.hash();
+			let hash_node = |(index, hash): (usize, &mut Hash)| {
+				let node_index = NodeIndex(level_start + index);
 
-			nodes.insert(hash, element);
+				*hash = hash_node(
+					&children[node_index.left_child().0 - level_end],
+					&children[node_index.right_child().0 - level_end],
+				);
+			};
 
-			intermediate_nodes.push_front(hash);
+			#[cfg(feature = "parallel")]
+			{
+				use rayon::prelude::*;
+
+				parents[level_start..].par_iter_mut().enumerate().for_each(hash_node);
+			}
+			#[cfg(not(feature = "parallel"))]
+			parents[level_start..].iter_mut().enumerate().for_each(hash_node);
+
+			level_end = level_start;
+		}
+
+		hashes
+	}
+
+	/// Returns the number of leaves.
+	pub fn num_leaves(&self) -> usize {
+		self.leaves.len()
+	}
+
+	/// Returns the number of inner nodes.
+	pub(crate) fn num_inner_nodes(&self) -> usize {
+		self.num_leaves().saturating_sub(1)
+	}
+
+	/// Returns the node index of the leaf at `leaf_index`.
+	fn leaf_node_index(&self, leaf_index: usize) -> NodeIndex {
+		// The leaves have the highest node indices.
+		NodeIndex(self.num_inner_nodes() + leaf_index)
+	}
+
+	/// Returns the index of the leaf for `type_id`.
+	fn leaf_index(&self, type_id: TypeId) -> Result<usize, String> {
+		self.type_ids
+			.binary_search_by_key(&type_id, |(type_id, _)| *type_id)
+			.map(|pos| self.type_ids[pos].1 as usize)
+			.map_err(|_| format!("Could not find leaf index for type id `{type_id:?}`"))
+	}
+
+	/// Returns the hash of the node at `node_index`.
+	fn hash(&self, node_index: NodeIndex) -> Result<Hash, String> {
+		self.hashes
+			.get(node_index.0)
+			.copied()
+			.ok_or_else(|| format!("Could not find hash for {node_index:?}"))
+	}
+
+	/// Replace the leaf of `type_id` with `ty`.
+	///
+	/// Only the hashes on the path from the leaf to the root are recalculated.
+	pub fn replace_leaf(&mut self, type_id: TypeId, ty: Type) -> Result<(), String> {
+		let leaf_index = self.leaf_index(type_id)?;
+
+		let mut node_index = self.leaf_node_index(leaf_index);
+		self.hashes[node_index.0] = blake3::hash(&ty.encode()).into();
+		self.leaves[leaf_index] = ty;
+
+		while !node_index.is_root() {
+			node_index = node_index.parent();
+
+			self.hashes[node_index.0] = hash_node(
+				&self.hash(node_index.left_child())?,
+				&self.hash(node_index.right_child())?,
+			);
 		}
 
-		let root_hash = intermediate_nodes.pop_back().unwrap_or_default();
-		hashes.push_front(root_hash);
-
-		Self {
-			root_hash,
-			nodes,
-			type_id_to_leaf_index,
-			node_index_to_hash: hashes
-				.into_iter()
-				.enumerate()
-				.map(|(i, h)| (NodeIndex(i), h))
-				.collect(),
+		Ok(())
+	}
+
+	/// Insert `ty` as new leaf of `type_id`.
+	///
+	/// The leaf is inserted at the position of `type_id` in the canonical order, so the tree stays
+	/// the same as [`Self::new`] with all leaves sorted by their [`TypeId`]. Returns an error if
+	/// the leaves of the tree are not in the canonical order.
+	///
+	/// This costs a full rebuild of the inner nodes, i.e. the same as [`Self::new`] without hashing
+	/// the existing leaves again. The tree is stored as a complete binary tree in a flat array and
+	/// adding a leaf moves every leaf to another node, so there are no unaffected paths that could
+	/// be kept. Use [`Self::replace_leaf`] to only recalculate the path of a single leaf.
+	pub fn insert_leaf(&mut self, type_id: TypeId, ty: Type) -> Result<(), String> {
+		let Err(leaf_index) = self.type_ids.binary_search_by_key(&type_id, |(type_id, _)| *type_id)
+		else {
+			return Err(format!("Leaf for type id `{type_id:?}` already exists"));
+		};
+
+		if !self.type_ids.iter().enumerate().all(|(pos, (_, index))| *index as usize == pos) {
+			return Err("Leaves can only be inserted into trees in the canonical order".into());
 		}
+
+		self.type_ids
+			.iter_mut()
+			.filter(|(_, index)| *index as usize >= leaf_index)
+			.for_each(|(_, index)| *index += 1);
+		self.type_ids.insert(leaf_index, (type_id, leaf_index as u32));
+
+		let mut leaf_hashes = self.hashes.split_off(self.num_inner_nodes());
+		leaf_hashes.insert(leaf_index, blake3::hash(&ty.encode()).into());
+		self.leaves.insert(leaf_index, ty);
+
+		self.hashes = Self::calculate_hashes(leaf_hashes);
+
+		Ok(())
+	}
+
+	/// Remove the leaf of `type_id` and return it.
+	///
+	/// All leaves after the removed leaf are shifted one position to the left. Like
+	/// [`Self::insert_leaf`], this costs a full rebuild of the inner nodes.
+	pub fn remove_leaf(&mut self, type_id: TypeId) -> Result<Type, String> {
+		let pos = self
+			.type_ids
+			.binary_search_by_key(&type_id, |(type_id, _)| *type_id)
+			.map_err(|_| format!("Could not find leaf index for type id `{type_id:?}`"))?;
+
+		let (_, leaf_index) = self.type_ids.remove(pos);
+		let leaf_index = leaf_index as usize;
+		self.type_ids
+			.iter_mut()
+			.filter(|(_, index)| *index as usize > leaf_index)
+			.for_each(|(_, index)| *index -= 1);
+
+		let mut leaf_hashes = self.hashes.split_off(self.num_inner_nodes());
+		leaf_hashes.remove(leaf_index);
+		let ty = self.leaves.remove(leaf_index);
+
+		self.hashes = Self::calculate_hashes(leaf_hashes);
+
+		Ok(ty)
 	}
 
 	/// Returns the root hash.
 	pub fn root(&self) -> Hash {
-		self.root_hash
+		self.hashes.first().copied().unwrap_or_default()
 	}
 
 	/// Build a proof that includes the given `type_ids`.
+	///
+	/// If `type_ids` is empty, the proof only contains the root hash as node. For an empty tree the
+	/// proof is empty.
 	pub fn build_proof(&self, type_ids: impl IntoIterator<Item = TypeId>) -> Result<Proof, String> {
 		let mut leaf_node_indices = Vec::new();
 
 		for type_id in type_ids.into_iter() {
-			let leaf_index = self
-				.type_id_to_leaf_index
-				.get(&type_id)
-				.ok_or_else(|| format!("Could not find leaf index for type id `{type_id:?}`"))?;
-			// The leaves have the highest node indices. Thus, we just need to
-			// subtract from the last node index the reverse index of the leaf.
-			let node_index =
-				self.nodes.len() - 1 - (self.type_id_to_leaf_index.len() - 1 - leaf_index);
-
-			leaf_node_indices.push(NodeIndex(node_index));
+			leaf_node_indices.push(self.leaf_node_index(self.leaf_index(type_id)?));
 		}
 
 		// Sort the leave node indices to get the left most leaf first.
 		leaf_node_indices.sort_by(|l, r| r.level().cmp(&l.level()).then_with(|| l.0.cmp(&r.0)));
+		leaf_node_indices.dedup();
 
 		let mut node_hashes = Vec::new();
 
 		let mut iter = leaf_node_indices.iter().peekable();
 
-		if let Some(leaf_node_index) = iter.next() {
-			self.collect_node_hashes(NodeIndex(0), *leaf_node_index, &mut iter, &mut node_hashes)?;
+		match iter.next() {
+			// A tree with a single leaf doesn't require any node hashes.
+			Some(leaf_node_index) if leaf_node_index.is_root() => {},
+			Some(leaf_node_index) => {
+				self.collect_node_hashes(
+					NodeIndex(0),
+					*leaf_node_index,
+					&mut iter,
+					&mut node_hashes,
+				)?;
+			},
+			None if !self.hashes.is_empty() => node_hashes.push(self.root()),
+			None => {},
 		}
 
 		let leaves = leaf_node_indices
 			.iter()
-			.map(|node_index| {
-				let hash = self
-					.node_index_to_hash
-					.get(node_index)
-					.ok_or_else(|| format!("Could not find hash for {node_index:?}"))?;
-				let node = self.nodes.get(hash).ok_or_else(|| {
-					format!("Could not find node for hash `{}`", array_bytes::bytes2hex("0x", hash))
-				})?;
-
-				match node {
-					MerkleTreeNode::Leaf { ty, .. } => Ok(ty.clone()),
-					MerkleTreeNode::Node { .. } => Err(format!(
-						"Expected leaf, found node for hash `{}`",
-						array_bytes::bytes2hex("0x", hash)
-					)),
-				}
-			})
-			.collect::<Result<Vec<_>, String>>()?;
+			.map(|node_index| self.leaves[node_index.0 - self.num_inner_nodes()].clone())
+			.collect();
 
 		Ok(Proof {
 			leaves,
@@ -308,7 +494,7 @@ impl MerkleTree {
 				let right_child = parent.right_child();
 
 				// If the right child is the next leaf, we can skip it.
-				if leaves.peek().map_or(false, |l| **l == right_child) {
+				if leaves.peek().is_some_and(|l| **l == right_child) {
 					// Skip the leaf
 					leaves.next();
 				}
@@ -324,20 +510,13 @@ impl MerkleTree {
 					self.collect_node_hashes(right_child, next_leaf, leaves, node_hashes)?;
 				} else {
 					// No need to go down this right child, so we need store the hash.
-					let hash = self.node_index_to_hash.get(&right_child).ok_or_else(|| {
-						format!("Could not find hash for right child `{right_child:?}`.")
-					})?;
-					node_hashes.push(*hash);
+					node_hashes.push(self.hash(right_child)?);
 				}
 			} else {
 				// As the leaves are sorted from left to right, the left child wasn't added yet.
 				let left_child = parent.left_child();
-				let hash = self.node_index_to_hash.get(&left_child).ok_or_else(|| {
-					format!("Could not find hash for left child `{left_child:?}`.")
-				})?;
-
 				// The left node should go to the left most position.
-				node_hashes.insert(left_most_hash_pos, *hash);
+				node_hashes.insert(left_most_hash_pos, self.hash(left_child)?);
 			}
 
 			if parent == stop_at_parent {
@@ -350,11 +529,15 @@ impl MerkleTree {
 }
 
 #[cfg(test)]
-mod tests {
-	use std::fs;
+pub(crate) mod tests {
+	use std::{
+		collections::{BTreeMap, BTreeSet},
+		fs,
+	};
 
 	use codec::Decode;
 	use frame_metadata::RuntimeMetadataPrefixed;
+	use proptest::prelude::*;
 
 	use super::*;
 	use crate::{
@@ -383,30 +566,33 @@ mod tests {
 
 			let merkle_tree = MerkleTree::new(types.clone());
 
-			let mut levels = BTreeMap::from_iter([(0, vec![merkle_tree.root_hash])]);
+			let mut levels = BTreeMap::from_iter([(0, vec![merkle_tree.root()])]);
 
 			fn collect_levels(
 				levels: &mut BTreeMap<u32, Vec<Hash>>,
 				level: u32,
 				merkle_tree: &MerkleTree,
-				node_hash: Hash,
+				node_index: NodeIndex,
 			) {
-				match merkle_tree.nodes.get(&node_hash).unwrap() {
-					MerkleTreeNode::Leaf { .. } => {},
-					MerkleTreeNode::Node { left, right } => {
-						levels.entry(level).or_default().push(*left);
-						levels.entry(level).or_default().push(*right);
-
-						collect_levels(levels, level + 1, merkle_tree, *left);
-						collect_levels(levels, level + 1, merkle_tree, *right);
-					},
+				if node_index.0 >= merkle_tree.num_inner_nodes() {
+					return;
 				}
+
+				let left = merkle_tree.hashes[node_index.left_child().0];
+				let right = merkle_tree.hashes[node_index.right_child().0];
+				assert_eq!(merkle_tree.hashes[node_index.0], hash_node(&left, &right));
+
+				levels.entry(level).or_default().push(left);
+				levels.entry(level).or_default().push(right);
+
+				collect_levels(levels, level + 1, merkle_tree, node_index.left_child());
+				collect_levels(levels, level + 1, merkle_tree, node_index.right_child());
 			}
 
-			collect_levels(&mut levels, 1, &merkle_tree, merkle_tree.root_hash);
+			collect_levels(&mut levels, 1, &merkle_tree, NodeIndex(0));
 			assert!(!levels.is_empty());
 			// Check that the numbers of levels is correct.
-			assert_eq!((merkle_tree.nodes.len() as f32).log2().ceil() as usize, levels.len());
+			assert_eq!((merkle_tree.hashes.len() as f32).log2().ceil() as usize, levels.len());
 
 			// Ensure it is a complete binary tree
 			while let Some((level, nodes)) = levels.pop_first() {
@@ -419,6 +605,247 @@ mod tests {
 		}
 	}
 
+	pub(crate) fn test_type(n: u32, len: u32) -> Type {
+		Type {
+			path: Vec::new(),
+			type_id: n.into(),
+			type_def: TypeDef::Array(TypeDefArray { len, type_param: TypeRef::U8 }),
+		}
+	}
+
+	/// Ensure that `merkle_tree` is the same as a fresh tree build from `leaves`.
+	fn assert_matches_fresh_tree(merkle_tree: &MerkleTree, leaves: &[(TypeId, Type)]) {
+		let fresh = MerkleTree::new(leaves.iter().cloned());
+
+		assert_eq!(fresh.root(), merkle_tree.root());
+		assert_eq!(fresh.hashes, merkle_tree.hashes);
+		assert_eq!(fresh.leaves, merkle_tree.leaves);
+		assert_eq!(fresh.type_ids, merkle_tree.type_ids);
+
+		let type_ids = leaves.iter().step_by(3).map(|(id, _)| *id).collect::<Vec<_>>();
+		assert_eq!(
+			fresh.build_proof(type_ids.iter().copied()).unwrap(),
+			merkle_tree.build_proof(type_ids).unwrap()
+		);
+	}
+
+	#[test]
+	fn incremental_updates_match_fresh_tree() {
+		for num_leaves in [1, 2, 3, 5, 8, 10, 23, 32] {
+			// Only even type ids, to insert the odd ones in between.
+			let mut leaves = (0..num_leaves)
+				.map(|n| (TypeId::Other(n * 2), test_type(n * 2, 1)))
+				.collect::<Vec<_>>();
+			let mut merkle_tree = MerkleTree::new(leaves.iter().cloned());
+
+			for index in 0..leaves.len() {
+				let type_id = leaves[index].0;
+				leaves[index].1 = test_type(type_id.type_id(), 2);
+				merkle_tree.replace_leaf(type_id, leaves[index].1.clone()).unwrap();
+				assert_matches_fresh_tree(&merkle_tree, &leaves);
+			}
+
+			for n in BTreeSet::from([num_leaves | 1, 1, num_leaves * 2 + 1]) {
+				let type_id = TypeId::Other(n);
+				merkle_tree.insert_leaf(type_id, test_type(n, 1)).unwrap();
+				leaves.push((type_id, test_type(n, 1)));
+				leaves.sort_by_key(|(type_id, _)| *type_id);
+				assert_matches_fresh_tree(&merkle_tree, &leaves);
+			}
+
+			while let Some((type_id, ty)) = leaves.get(leaves.len() / 2).cloned() {
+				assert_eq!(ty, merkle_tree.remove_leaf(type_id).unwrap());
+				leaves.retain(|(id, _)| *id != type_id);
+				assert_matches_fresh_tree(&merkle_tree, &leaves);
+			}
+
+			assert_eq!(0, merkle_tree.num_leaves());
+			assert_eq!(Hash::default(), merkle_tree.root());
+		}
+	}
+
+	#[test]
+	fn incremental_updates_reject_invalid_input() {
+		let leaves = (0..5).map(|n| (TypeId::Other(n), test_type(n, 1))).collect::<Vec<_>>();
+		let mut merkle_tree = MerkleTree::new(leaves.iter().cloned());
+
+		assert!(merkle_tree.replace_leaf(TypeId::Other(10), test_type(10, 1)).is_err());
+		assert!(merkle_tree.remove_leaf(TypeId::Other(10)).is_err());
+		assert!(merkle_tree.insert_leaf(TypeId::Other(1), test_type(1, 1)).is_err());
+
+		assert_matches_fresh_tree(&merkle_tree, &leaves);
+
+		// Leaves can not be inserted into trees that are not in the canonical order.
+		let mut merkle_tree = MerkleTree::new(leaves.iter().rev().cloned());
+		assert!(merkle_tree.insert_leaf(TypeId::Other(10), test_type(10, 1)).is_err());
+	}
+
+	#[test]
+	fn replace_leaf_in_real_metadata() {
+		let metadata = crate::tests::load_metadata("rococo_metadata_v15");
+		let mut leaves = crate::generate_type_information(&metadata)
+			.unwrap()
+			.types
+			.into_iter()
+			.collect::<Vec<_>>();
+		let mut merkle_tree = MerkleTree::new(leaves.iter().cloned());
+
+		let index = leaves.len() / 3;
+		let (type_id, ty) = &mut leaves[index];
+		ty.path.push("Changed".into());
+		merkle_tree.replace_leaf(*type_id, ty.clone()).unwrap();
+
+		assert_matches_fresh_tree(&merkle_tree, &leaves);
+	}
+
+	#[test]
+	fn proof_root_works() {
+		for num_leaves in [1, 2, 3, 5, 8, 10, 23] {
+			let leaves = (0..num_leaves).map(|n| (TypeId::Other(n), test_type(n, 1)));
+			let merkle_tree = MerkleTree::new(leaves);
+
+			for leaf in 0..num_leaves {
+				let type_ids = [TypeId::Other(leaf), TypeId::Other((leaf * 7) % num_leaves)];
+				let proof = merkle_tree.build_proof(type_ids).unwrap();
+				assert_eq!(merkle_tree.root(), proof.root().unwrap());
+			}
+		}
+	}
+
+	#[test]
+	fn proof_root_rejects_malformed_proofs() {
+		let merkle_tree = MerkleTree::new((0..10).map(|n| (TypeId::Other(n), test_type(n, 1))));
+		let proof = merkle_tree
+			.build_proof([TypeId::Other(1), TypeId::Other(4), TypeId::Other(5)])
+			.unwrap();
+		assert_eq!(merkle_tree.root(), proof.root().unwrap());
+
+		type Mutation = (&'static str, fn(&mut Proof));
+
+		let mutations: [Mutation; 9] = [
+			("missing leaf index", |p| {
+				p.leaf_indices.pop();
+			}),
+			("missing leaf", |p| {
+				p.leaves.pop();
+			}),
+			("missing node", |p| {
+				p.nodes.pop();
+			}),
+			("additional node", |p| p.nodes.push(Hash::default())),
+			("duplicated leaf", |p| {
+				p.leaves.push(p.leaves[0].clone());
+				p.leaf_indices.push(p.leaf_indices[0]);
+			}),
+			("unsorted leaves", |p| {
+				p.leaves.reverse();
+				p.leaf_indices.reverse();
+			}),
+			("leaf index of an inner node", |p| p.leaf_indices[0] = 1),
+			("leaf index out of bounds", |p| p.leaf_indices[2] = u32::MAX),
+			("no nodes", |p| p.nodes.clear()),
+		];
+
+		for (name, mutate) in mutations {
+			let mut proof = proof.clone();
+			mutate(&mut proof);
+
+			assert!(proof.root() != Ok(merkle_tree.root()), "Accepted proof with {name}");
+		}
+	}
+
+	#[test]
+	fn empty_tree() {
+		let merkle_tree = MerkleTree::new([]);
+		assert_eq!(Hash::default(), merkle_tree.root());
+
+		let proof = merkle_tree.build_proof([]).unwrap();
+		assert_eq!(
+			Proof { leaves: Vec::new(), leaf_indices: Vec::new(), nodes: Vec::new() },
+			proof
+		);
+		assert_eq!(Hash::default(), proof.root().unwrap());
+
+		assert!(merkle_tree.build_proof([TypeId::Other(0)]).is_err());
+	}
+
+	#[test]
+	fn single_leaf_tree() {
+		let leaf = test_type(0, 1);
+		let merkle_tree = MerkleTree::new([(TypeId::Other(0), leaf.clone())]);
+		assert_eq!(<Hash>::from(blake3::hash(&leaf.encode())), merkle_tree.root());
+
+		let proof = merkle_tree.build_proof([TypeId::Other(0)]).unwrap();
+		assert_eq!(Proof { leaves: vec![leaf], leaf_indices: vec![0], nodes: Vec::new() }, proof);
+		assert_eq!(merkle_tree.root(), proof.root().unwrap());
+
+		let proof = merkle_tree.build_proof([]).unwrap();
+		assert_eq!(
+			Proof { leaves: Vec::new(), leaf_indices: Vec::new(), nodes: vec![merkle_tree.root()] },
+			proof
+		);
+		assert_eq!(merkle_tree.root(), proof.root().unwrap());
+	}
+
+	/// Returns a number of leaves and a random subset of them.
+	pub(crate) fn tree_and_leaves() -> impl Strategy<Value = (u32, Vec<u32>)> {
+		prop_oneof![Just(1u32), (0..=10u32).prop_map(|e| 1 << e), 1..=2000u32].prop_flat_map(
+			|num_leaves| {
+				let leaves = (0..num_leaves).collect::<Vec<_>>();
+				let max = leaves.len().min(64);
+				(Just(num_leaves), proptest::sample::subsequence(leaves, 0..=max))
+			},
+		)
+	}
+
+	proptest::proptest! {
+		#![proptest_config(ProptestConfig::with_cases(64))]
+
+		#[test]
+		fn proofs_for_random_leaves_work((num_leaves, leaves) in tree_and_leaves()) {
+			let merkle_tree =
+				MerkleTree::new((0..num_leaves).map(|n| (TypeId::Other(n), test_type(n, 1))));
+			let root = merkle_tree.root();
+
+			let proof = merkle_tree.build_proof(leaves.iter().map(|l| TypeId::Other(*l))).unwrap();
+			prop_assert_eq!(leaves.len(), proof.leaves.len());
+			prop_assert_eq!(root, proof.root().unwrap());
+			prop_assert_eq!(
+				root,
+				get_hash(
+					&mut &proof.leaf_indices[..],
+					&mut &proof.leaves[..],
+					&mut &proof.nodes[..],
+					NodeIndex(0),
+					&merkle_tree,
+				)
+			);
+
+			let rejected = |proof: &Proof| proof.root() != Ok(root);
+
+			for i in 0..proof.nodes.len() {
+				let mut altered = proof.clone();
+				altered.nodes[i][i % 32] ^= 1;
+				prop_assert!(rejected(&altered), "Accepted altered node {}", i);
+
+				let mut removed = proof.clone();
+				removed.nodes.remove(i);
+				prop_assert!(rejected(&removed), "Accepted removed node {}", i);
+			}
+
+			for i in 0..proof.leaves.len() {
+				let mut altered = proof.clone();
+				altered.leaves[i].type_id.0 += num_leaves;
+				prop_assert!(rejected(&altered), "Accepted altered leaf {}", i);
+
+				let mut removed = proof.clone();
+				removed.leaves.remove(i);
+				removed.leaf_indices.remove(i);
+				prop_assert!(rejected(&removed), "Accepted removed leaf {}", i);
+			}
+		}
+	}
+
 	fn get_hash(
 		leaf_indices: &mut &[u32],
 		leaves: &mut &[Type],
@@ -451,29 +878,21 @@ mod tests {
 		let left_child = node_index.left_child();
 		let left = get_hash(leaf_indices, leaves, nodes, left_child, merkle_tree);
 
-		assert_eq!(
-			left,
-			*merkle_tree.node_index_to_hash.get(&left_child).unwrap(),
-			"Found wrong {left_child:?}"
-		);
+		assert_eq!(left, merkle_tree.hashes[left_child.0], "Found wrong {left_child:?}");
 
 		let right_child = node_index.right_child();
 		let right = get_hash(leaf_indices, leaves, nodes, right_child, merkle_tree);
 
-		assert_eq!(
-			right,
-			*merkle_tree.node_index_to_hash.get(&right_child).unwrap(),
-			"Found wrong {right_child:?}"
-		);
+		assert_eq!(right, merkle_tree.hashes[right_child.0], "Found wrong {right_child:?}");
 
 		blake3::hash(&(left, right).encode()).into()
 	}
 
 	// `Balances::transfer_keep_alive`
-	const TEST_EXT: &str = "0x2d028400d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d01bce7c8f572d39cee240e3d50958f68a5c129e0ac0d4eb9222de70abdfa8c44382a78eded433782e6b614a97d8fd609a3f20162f3f3b3c16e7e8489b2bd4fa98c070000000403008eaf04151687736326c9fea17e25fc5287613693c912909cb226aa4794f26a4828";
-	const TEST_CALL: &str =
+	pub(crate) const TEST_EXT: &str = "0x2d028400d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d01bce7c8f572d39cee240e3d50958f68a5c129e0ac0d4eb9222de70abdfa8c44382a78eded433782e6b614a97d8fd609a3f20162f3f3b3c16e7e8489b2bd4fa98c070000000403008eaf04151687736326c9fea17e25fc5287613693c912909cb226aa4794f26a4828";
+	pub(crate) const TEST_CALL: &str =
 		"0x04030052bc71c1eca5353749542dfdf0af97bf764f9c2f44e860cd485f1cd86400f6490f0080c6a47e8d03";
-	const TEST_ADDITIONAL_SIGNED: &str = "0x00b2590f001800000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000";
+	pub(crate) const TEST_ADDITIONAL_SIGNED: &str = "0xb2590f001800000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000";
 
 	#[test]
 	fn generate_proof() {
@@ -507,7 +926,7 @@ mod tests {
 		let accessed_types = decode_extrinsic_and_collect_type_ids(
 			ext_ptr,
 			Some(&array_bytes::hex2bytes(TEST_ADDITIONAL_SIGNED).unwrap()),
-			&type_information,
+			&type_information.extrinsic_metadata,
 			proof.leaves.iter(),
 		)
 		.unwrap();
@@ -606,7 +1025,7 @@ mod tests {
 		decode_extrinsic_and_collect_type_ids(
 			&mut &array_bytes::hex2bytes(TEST_EXT).unwrap()[..],
 			Some(&array_bytes::hex2bytes(TEST_ADDITIONAL_SIGNED).unwrap()),
-			&type_information,
+			&type_information.extrinsic_metadata,
 			proof.leaves.iter(),
 		)
 		.unwrap();
@@ -0,0 +1,449 @@
+//! Allow and deny rules for the calls of an extrinsic.
+
+use alloc::{string::String, vec::Vec};
+use core::cmp::Ordering;
+
+use crate::{
+	extrinsic_decoder::{decode_extrinsic_parts, ExtrinsicPart, TypeResolver},
+	merkle_tree::Proof,
+	types::ExtrinsicMetadata,
+	value::{decode_calls, decode_value, Value},
+};
+
+/// The action of a [`Rule`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+	Allow,
+	Deny,
+}
+
+/// A rule of a [`Policy`].
+///
+/// The rule matches a call if the pallet and the call name match and all `conditions` hold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+	/// The name of the rule, for reporting.
+	pub name: String,
+	pub action: Action,
+	/// The name of the pallet, `None` matches any pallet.
+	pub pallet: Option<String>,
+	/// The name of the call, `None` matches any call of the pallet.
+	pub call: Option<String>,
+	pub conditions: Vec<Condition>,
+}
+
+/// A condition on a field of a call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condition {
+	/// The path to the field, starting at the arguments of the call.
+	///
+	/// Each element is the name of a field or the index into a sequence or unnamed fields, e.g.
+	/// `["calls", "0"]`. Composites with exactly one field are looked through.
+	pub path: Vec<String>,
+	pub matcher: Matcher,
+}
+
+/// Matches the value of a field.
+///
+/// A condition doesn't hold if the field doesn't exist. If the field exists, but the matcher can't
+/// interpret its value, e.g. a `MultiAddress::Index` for the account matchers, the condition is
+/// indeterminate, see [`Rule::matches`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Matcher {
+	/// The field exists.
+	Exists,
+	/// The unsigned integer equals the given one.
+	///
+	/// Works for integers of up to 256 bits.
+	Equals(u128),
+	/// The unsigned integer is greater than the given one.
+	GreaterThan(u128),
+	/// The unsigned integer is less than the given one.
+	LessThan(u128),
+	/// The account id is one of the given ones, see [`Value::as_account_id`].
+	AccountIn(Vec<Vec<u8>>),
+	/// The account id is not one of the given ones, see [`Value::as_account_id`].
+	///
+	/// Addresses without an account id, like `MultiAddress::Index` or `MultiAddress::Raw`, are
+	/// indeterminate.
+	AccountNotIn(Vec<Vec<u8>>),
+}
+
+/// The decision of a [`Policy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decision<'a> {
+	pub action: Action,
+	/// The rule that matched, `None` if the default action was taken.
+	pub rule: Option<&'a Rule>,
+}
+
+/// A list of rules that decides whether calls are allowed.
+///
+/// The first matching rule decides, if no rule matches the `default` action is taken.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Policy {
+	pub rules: Vec<Rule>,
+	pub default: Action,
+}
+
+impl Policy {
+	/// Evaluate the policy for the given decoded `call`.
+	///
+	/// Only the `call` itself is checked, not the calls nested in it.
+	pub fn evaluate_call(&self, call: &Value) -> Decision<'_> {
+		self.rules.iter().find(|rule| rule.matches(call)).map_or(
+			Decision { action: self.default, rule: None },
+			|rule| Decision { action: rule.action, rule: Some(rule) },
+		)
+	}
+
+	/// Evaluate the policy for all calls of the given `extrinsic`.
+	///
+	/// The `extrinsic` is decoded using only the leaves of `proof`. Every call is evaluated,
+	/// including the calls nested in other calls, see
+	/// [`decode_call_tree`](crate::decode_call_tree). The extrinsic is denied if any of the calls
+	/// is denied, the decision of the first denied call is returned. Otherwise the decision of the
+	/// call of the extrinsic is returned.
+	///
+	/// Returns an error if a call is passed as opaque bytes or by its hash, e.g. the proposal of
+	/// `Referenda::submit`, as it can not be evaluated.
+	pub fn evaluate(
+		&self,
+		mut extrinsic: &[u8],
+		extrinsic_metadata: &ExtrinsicMetadata,
+		proof: &Proof,
+	) -> Result<Decision<'_>, String> {
+		let type_resolver = TypeResolver::new(proof.leaves.iter());
+
+		let (calls, _) = decode_extrinsic_parts(
+			&mut extrinsic,
+			extrinsic_metadata,
+			Vec::new(),
+			|calls, input, part, type_ref| match part {
+				ExtrinsicPart::Call => decode_calls(input, type_ref, &type_resolver),
+				_ => decode_value(input, type_ref, &type_resolver).map(|_| calls),
+			},
+		)?;
+
+		if !extrinsic.is_empty() {
+			return Err("Bytes left in `extrinsic` after decoding".into());
+		}
+
+		let mut decision = None;
+
+		for call in &calls {
+			let call_decision = self.evaluate_call(call);
+			if call_decision.action == Action::Deny {
+				return Ok(call_decision);
+			}
+
+			decision.get_or_insert(call_decision);
+		}
+
+		decision.ok_or_else(|| "Extrinsic without a call".into())
+	}
+}
+
+impl Rule {
+	/// Returns `true` if the rule matches the given decoded `call`.
+	///
+	/// Indeterminate conditions fail closed: they hold for [`Action::Deny`] rules and don't hold
+	/// for [`Action::Allow`] rules.
+	pub fn matches(&self, call: &Value) -> bool {
+		let Some((pallet, call)) = call_parts(call) else { return false };
+
+		self.pallet.as_ref().is_none_or(|p| p == pallet) &&
+			self.call.as_ref().is_none_or(|c| Some(c.as_str()) == call.variant_name()) &&
+			self.conditions
+				.iter()
+				.all(|c| c.holds(call).unwrap_or(self.action == Action::Deny))
+	}
+}
+
+impl Condition {
+	/// Returns whether the condition holds for the arguments of `call`.
+	///
+	/// Returns `None` if the condition is indeterminate.
+	fn holds(&self, call: &Value) -> Option<bool> {
+		let Some(value) = self.path.iter().try_fold(call, |value, segment| field(value, segment))
+		else {
+			return Some(false)
+		};
+
+		match &self.matcher {
+			Matcher::Exists => Some(true),
+			Matcher::Equals(expected) => compare(value, *expected).map(Ordering::is_eq),
+			Matcher::GreaterThan(limit) => compare(value, *limit).map(Ordering::is_gt),
+			Matcher::LessThan(limit) => compare(value, *limit).map(Ordering::is_lt),
+			Matcher::AccountIn(accounts) =>
+				value.as_account_id().map(|account| accounts.contains(&account)),
+			Matcher::AccountNotIn(accounts) =>
+				value.as_account_id().map(|account| !accounts.contains(&account)),
+		}
+	}
+}
+
+/// Compares the unsigned integer `value` to `other`.
+///
+/// Returns `None` if `value` isn't an unsigned integer.
+fn compare(value: &Value, other: u128) -> Option<Ordering> {
+	match value.inner() {
+		Value::Unsigned(value) => Some(value.cmp(&other)),
+		Value::U256(value) => {
+			let (low, high) = value.split_at(16);
+			if high.iter().any(|b| *b != 0) {
+				return Some(Ordering::Greater)
+			}
+
+			let low = u128::from_le_bytes(low.try_into().expect("Splits at 16 bytes; qed"));
+			Some(low.cmp(&other))
+		},
+		_ => None,
+	}
+}
+
+/// Returns the name of the pallet and the variant of the call.
+fn call_parts(call: &Value) -> Option<(&str, &Value)> {
+	match call {
+		Value::Variant { name, fields, .. } => match &fields[..] {
+			[(_, call)] => Some((name, call)),
+			_ => None,
+		},
+		_ => None,
+	}
+}
+
+/// Returns the field `segment` of `value`.
+fn field<'a>(value: &'a Value, segment: &str) -> Option<&'a Value> {
+	if let Some(field) = value.field(segment) {
+		return Some(field)
+	}
+
+	match (value, segment.parse::<usize>()) {
+		// Look through wrappers like `AccountId32` or `Perbill`.
+		(Value::Composite(fields), _) if fields.len() == 1 => field(&fields[0].1, segment),
+		(Value::Composite(fields) | Value::Variant { fields, .. }, Ok(index)) =>
+			fields.get(index).map(|(_, value)| value),
+		(Value::Sequence(values), Ok(index)) => values.get(index),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		generate_proof_for_extrinsic, generate_type_information,
+		tests::{call_index, load_metadata},
+	};
+	use alloc::vec;
+	use codec::{Compact, Encode};
+
+	fn rule(name: &str, action: Action, pallet: &str, call: Option<&str>) -> Rule {
+		Rule {
+			name: name.into(),
+			action,
+			pallet: Some(pallet.into()),
+			call: call.map(Into::into),
+			conditions: Vec::new(),
+		}
+	}
+
+	fn condition(path: &[&str], matcher: Matcher) -> Condition {
+		Condition { path: path.iter().map(|s| String::from(*s)).collect(), matcher }
+	}
+
+	#[test]
+	fn policy_works() {
+		let metadata = load_metadata("rococo_metadata_v15");
+		let type_information = generate_type_information(&metadata).unwrap();
+		let extrinsic_metadata = &type_information.extrinsic_metadata;
+		let call_index = |pallet, call| call_index(&type_information, pallet, call);
+
+		let delegate = [2u8; 32];
+		let policy = Policy {
+			rules: vec![
+				rule("no set_code", Action::Deny, "System", Some("set_code")),
+				rule("no sudo", Action::Deny, "Sudo", None),
+				Rule {
+					conditions: vec![condition(&["value"], Matcher::GreaterThan(100))],
+					..rule("transfer limit", Action::Deny, "Balances", Some("transfer_keep_alive"))
+				},
+				Rule {
+					conditions: vec![condition(
+						&["real"],
+						Matcher::AccountNotIn(vec![delegate.to_vec()]),
+					)],
+					..rule("unknown delegate", Action::Deny, "Proxy", Some("proxy"))
+				},
+				rule("batches", Action::Allow, "Utility", Some("batch_all")),
+			],
+			default: Action::Allow,
+		};
+
+		// Returns the action and the name of the matched rule.
+		let evaluate = |call: Vec<u8>| {
+			let extrinsic = [&[4u8][..], &call].concat().encode();
+			let proof = generate_proof_for_extrinsic(&extrinsic, None, &metadata).unwrap();
+			let decision = policy.evaluate(&extrinsic, extrinsic_metadata, &proof).unwrap();
+
+			(decision.action, decision.rule.map(|r| r.name.as_str()))
+		};
+		let batch = |calls: Vec<Vec<u8>>| {
+			[
+				call_index("Utility", "batch_all").encode(),
+				Compact(calls.len() as u32).encode(),
+				calls.concat(),
+			]
+			.concat()
+		};
+		let transfer = |value: u128| {
+			(call_index("Balances", "transfer_keep_alive"), 0u8, [1u8; 32], Compact(value)).encode()
+		};
+		let proxy_address = |real: Vec<u8>, call: Vec<u8>| {
+			[call_index("Proxy", "proxy").encode(), real, None::<u8>.encode(), call].concat()
+		};
+		let proxy = |real: [u8; 32], call: Vec<u8>| proxy_address((0u8, real).encode(), call);
+		let remark = (call_index("System", "remark"), b"RFC78".to_vec()).encode();
+		let set_code_bytes = || (call_index("System", "set_code"), vec![1u8; 4]).encode();
+		let set_code = set_code_bytes();
+		let sudo = [call_index("Sudo", "sudo").encode(), remark.clone()].concat();
+
+		assert_eq!((Action::Allow, None), evaluate(remark.clone()));
+		assert_eq!((Action::Allow, None), evaluate(transfer(100)));
+		assert_eq!((Action::Deny, Some("transfer limit")), evaluate(transfer(101)));
+		assert_eq!((Action::Deny, Some("no set_code")), evaluate(set_code));
+		assert_eq!((Action::Deny, Some("no sudo")), evaluate(sudo.clone()));
+
+		// The nested calls are evaluated as well.
+		assert_eq!(
+			(Action::Allow, Some("batches")),
+			evaluate(batch(vec![proxy(delegate, transfer(100)), remark.clone()]))
+		);
+		assert_eq!(
+			(Action::Deny, Some("transfer limit")),
+			evaluate(batch(vec![proxy(delegate, transfer(101)), remark.clone()]))
+		);
+		assert_eq!(
+			(Action::Deny, Some("unknown delegate")),
+			evaluate(batch(vec![proxy([3; 32], transfer(10))]))
+		);
+		assert_eq!((Action::Deny, Some("no sudo")), evaluate(batch(vec![remark.clone(), sudo])));
+
+		// Addresses without an account id can't be checked and are denied.
+		let index = 1u8.encode();
+		let raw = (2u8, delegate.to_vec()).encode();
+		assert_eq!(
+			(Action::Deny, Some("unknown delegate")),
+			evaluate(proxy_address(index, transfer(10)))
+		);
+		assert_eq!(
+			(Action::Deny, Some("unknown delegate")),
+			evaluate(proxy_address(raw, transfer(10)))
+		);
+
+		// Calls passed as opaque bytes can't be evaluated.
+		let submit = [
+			call_index("Referenda", "submit").encode(),
+			// `system(Root)` origin.
+			(0u8, 0u8).encode(),
+			// `Bounded::Inline` proposal.
+			(1u8, set_code_bytes()).encode(),
+			// `DispatchTime::After`.
+			(1u8, 10u32).encode(),
+		]
+		.concat();
+		let extrinsic = [&[4u8][..], &submit].concat().encode();
+		let proof = generate_proof_for_extrinsic(&extrinsic, None, &metadata).unwrap();
+		assert_eq!(
+			Err("Failed to decode call: Opaque call `frame_support::traits::preimages::Bounded` can \
+				 not be collected"
+				.into()),
+			policy.evaluate(&extrinsic, extrinsic_metadata, &proof)
+		);
+	}
+
+	#[test]
+	fn field_paths_work() {
+		let account = Value::Composite(vec![(None, Value::Sequence(vec![Value::Unsigned(1); 32]))]);
+		let call = Value::Variant {
+			name: "Pallet".into(),
+			index: 0,
+			fields: vec![(
+				None,
+				Value::Variant {
+					name: "call".into(),
+					index: 0,
+					fields: vec![
+						(Some("who".into()), account),
+						(
+							Some("amounts".into()),
+							Value::Sequence(vec![Value::Unsigned(5), Value::Unsigned(7)]),
+						),
+						(
+							Some("limit".into()),
+							Value::Variant {
+								name: "Some".into(),
+								index: 1,
+								fields: vec![(None, Value::Unsigned(3))],
+							},
+						),
+					],
+				},
+			)],
+		};
+
+		let holds = |path: &[&str], matcher: Matcher| {
+			condition(path, matcher).holds(call_parts(&call).unwrap().1)
+		};
+
+		assert_eq!(Some(true), holds(&["who"], Matcher::AccountIn(vec![vec![1; 32]])));
+		assert_eq!(Some(false), holds(&["who"], Matcher::AccountNotIn(vec![vec![1; 32]])));
+		assert_eq!(Some(true), holds(&["who", "0"], Matcher::Equals(1)));
+		assert_eq!(Some(true), holds(&["amounts", "1"], Matcher::Equals(7)));
+		assert_eq!(Some(true), holds(&["amounts", "0"], Matcher::LessThan(6)));
+		assert_eq!(Some(false), holds(&["amounts", "2"], Matcher::Exists));
+		assert_eq!(Some(true), holds(&["limit", "0"], Matcher::GreaterThan(2)));
+		assert_eq!(None, holds(&["limit"], Matcher::GreaterThan(2)));
+		assert_eq!(None, holds(&["amounts", "0"], Matcher::AccountNotIn(vec![vec![1; 32]])));
+		assert_eq!(Some(false), holds(&["unknown"], Matcher::Exists));
+
+		let mut large = [0u8; 32];
+		large[0] = 5;
+		assert_eq!(Some(true), condition(&[], Matcher::Equals(5)).holds(&Value::U256(large)));
+		large[20] = 1;
+		assert_eq!(
+			Some(true),
+			condition(&[], Matcher::GreaterThan(u128::MAX)).holds(&Value::U256(large))
+		);
+		assert_eq!(Some(false), condition(&[], Matcher::LessThan(6)).holds(&Value::U256(large)));
+
+		// Indeterminate conditions only hold for deny rules.
+		let index = Value::Variant {
+			name: "Pallet".into(),
+			index: 0,
+			fields: vec![(
+				None,
+				Value::Variant {
+					name: "call".into(),
+					index: 0,
+					fields: vec![(
+						Some("who".into()),
+						Value::Variant {
+							name: "Index".into(),
+							index: 1,
+							fields: vec![(None, Value::Composite(Vec::new()))],
+						},
+					)],
+				},
+			)],
+		};
+		for (action, matches) in [(Action::Deny, true), (Action::Allow, false)] {
+			let rule = Rule {
+				conditions: vec![condition(&["who"], Matcher::AccountIn(vec![vec![1; 32]]))],
+				..rule("who", action, "Pallet", Some("call"))
+			};
+			assert_eq!(matches, rule.matches(&index));
+		}
+	}
+}
@@ -0,0 +1,104 @@
+use alloc::{collections::BTreeMap, string::String};
+use codec::Encode;
+
+use crate::merkle_tree::Proof;
+
+/// Information about the size of a [`Proof`].
+///
+/// The breakdowns only account for the bytes of the `leaves`, as the node hashes can not be
+/// attributed to any specific type.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProofSizeReport {
+	/// The number of leaves in the proof.
+	pub leaf_count: usize,
+	/// The number of node hashes in the proof.
+	pub node_count: usize,
+	/// The size of the SCALE encoded proof in bytes.
+	pub encoded_size: usize,
+	/// The encoded size of the leaves grouped by the pallet that declares the type.
+	///
+	/// The pallet is the first segment of the type path, e.g. `pallet_balances`. Types without a
+	/// path (tuples, sequences, etc) are grouped under an empty string.
+	pub bytes_by_pallet: BTreeMap<String, usize>,
+	/// The encoded size of the leaves grouped by the type path, e.g.
+	/// `pallet_balances::pallet::Call`. Types without a path are grouped under an empty string.
+	pub bytes_by_type_path: BTreeMap<String, usize>,
+}
+
+impl Proof {
+	/// Returns the [`ProofSizeReport`] for this proof.
+	pub fn size_report(&self) -> ProofSizeReport {
+		let mut report = ProofSizeReport {
+			leaf_count: self.leaves.len(),
+			node_count: self.nodes.len(),
+			encoded_size: self.encoded_size(),
+			..Default::default()
+		};
+
+		for leaf in &self.leaves {
+			let size = leaf.encoded_size();
+
+			let pallet = leaf.path.first().cloned().unwrap_or_default();
+			*report.bytes_by_pallet.entry(pallet).or_default() += size;
+			*report.bytes_by_type_path.entry(leaf.path.join("::")).or_default() += size;
+		}
+
+		report
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		generate_proof_for_extrinsic, generate_proof_for_type_ids, generate_type_information,
+		merkle_tree::tests::{TEST_ADDITIONAL_SIGNED, TEST_EXT},
+		tests::load_metadata,
+	};
+	use codec::Encode;
+
+	#[test]
+	fn size_report_works() {
+		let metadata = load_metadata("rococo_metadata_v15");
+
+		let proof = generate_proof_for_extrinsic(
+			&array_bytes::hex2bytes(TEST_EXT).unwrap(),
+			Some(&array_bytes::hex2bytes(TEST_ADDITIONAL_SIGNED).unwrap()),
+			&metadata,
+		)
+		.unwrap();
+
+		let report = proof.size_report();
+
+		assert_eq!(proof.leaves.len(), report.leaf_count);
+		assert_eq!(proof.nodes.len(), report.node_count);
+		assert_eq!(proof.encode().len(), report.encoded_size);
+
+		let leaves_size = proof.leaves.iter().map(|l| l.encoded_size()).sum::<usize>();
+		assert_eq!(leaves_size, report.bytes_by_pallet.values().sum::<usize>());
+		assert_eq!(leaves_size, report.bytes_by_type_path.values().sum::<usize>());
+		assert!(report.bytes_by_pallet.contains_key("pallet_balances"));
+		assert!(report.bytes_by_type_path.contains_key("pallet_balances::pallet::Call"));
+	}
+
+	#[test]
+	fn proof_for_type_ids_matches_proof_for_extrinsic() {
+		let metadata = load_metadata("rococo_metadata_v15");
+
+		let proof = generate_proof_for_extrinsic(
+			&array_bytes::hex2bytes(TEST_EXT).unwrap(),
+			Some(&array_bytes::hex2bytes(TEST_ADDITIONAL_SIGNED).unwrap()),
+			&metadata,
+		)
+		.unwrap();
+
+		let type_information = generate_type_information(&metadata).unwrap();
+		let type_ids = type_information
+			.types
+			.iter()
+			.filter(|(_, ty)| proof.leaves.contains(ty))
+			.map(|(id, _)| *id)
+			.collect::<Vec<_>>();
+
+		assert_eq!(proof, generate_proof_for_type_ids(type_ids, &metadata).unwrap());
+	}
+}
@@ -0,0 +1,286 @@
+//! A deliberately simple reference implementation of the merkle tree and its proofs.
+//!
+//! It follows the description of RFC78 and doesn't care about performance. The tests compare it
+//! with [`MerkleTree`] to catch bugs in the optimised implementation.
+
+use std::{
+	cmp::Reverse,
+	collections::{BTreeMap, BTreeSet, VecDeque},
+};
+
+use codec::Encode;
+use proptest::prelude::*;
+
+use crate::{
+	generate_type_information,
+	merkle_tree::{
+		tests::{test_type, tree_and_leaves, TEST_ADDITIONAL_SIGNED, TEST_EXT},
+		MerkleTree, Proof, TypeId,
+	},
+	tests::{load_metadata, FIXTURES},
+	types::{Hash, Type},
+};
+
+fn hash_leaf(leaf: &Type) -> Hash {
+	blake3::hash(&leaf.encode()).into()
+}
+
+fn hash_node(left: &Hash, right: &Hash) -> Hash {
+	blake3::hash(&(left, right).encode()).into()
+}
+
+fn parent(node_index: u64) -> u64 {
+	(node_index - 1) / 2
+}
+
+fn level(node_index: u64) -> u32 {
+	(node_index + 1).ilog2()
+}
+
+/// The order of the leaves in a proof: deepest level first, then from left to right.
+fn leaf_order(node_index: u64) -> (Reverse<u32>, u64) {
+	(Reverse(level(node_index)), node_index)
+}
+
+/// Returns all the ancestors of `leaves`.
+fn ancestors(leaves: &[u64]) -> BTreeSet<u64> {
+	let mut ancestors = BTreeSet::new();
+
+	for leaf in leaves {
+		let mut node_index = *leaf;
+		while node_index != 0 {
+			node_index = parent(node_index);
+			ancestors.insert(node_index);
+		}
+	}
+
+	ancestors
+}
+
+/// Returns the nodes that can not be calculated out of `leaves`, ordered from left to right.
+fn required_nodes(leaves: &[u64]) -> Vec<u64> {
+	if leaves.is_empty() {
+		return vec![0];
+	}
+
+	let ancestors = ancestors(leaves);
+
+	let mut required = ancestors
+		.iter()
+		.flat_map(|a| [a * 2 + 1, a * 2 + 2])
+		.filter(|c| !ancestors.contains(c) && !leaves.contains(c))
+		.collect::<Vec<_>>();
+
+	// None of the nodes is an ancestor of another one. So, their left most descendants at the
+	// same level are ordered like the nodes.
+	required.sort_by_key(|n| u128::from(n + 1) << (64 - level(*n)));
+
+	required
+}
+
+/// The merkle tree, build the way RFC78 describes it.
+struct ReferenceTree {
+	leaves: Vec<Type>,
+	/// The hashes of all nodes, indexed by the node index.
+	hashes: Vec<Hash>,
+}
+
+impl ReferenceTree {
+	/// Build the tree by taking the two last nodes of the queue and putting their parent at the
+	/// front of the queue, until only the root is left.
+	fn new(leaves: Vec<Type>) -> Self {
+		let num_leaves = leaves.len();
+		let mut hashes = vec![Hash::default(); (num_leaves * 2).saturating_sub(1)];
+
+		let mut queue = leaves
+			.iter()
+			.enumerate()
+			.map(|(i, leaf)| (num_leaves - 1 + i, hash_leaf(leaf)))
+			.collect::<VecDeque<_>>();
+		let mut next_node_index = num_leaves.saturating_sub(1);
+
+		while queue.len() > 1 {
+			let (right_index, right) = queue.pop_back().unwrap();
+			let (left_index, left) = queue.pop_back().unwrap();
+			hashes[right_index] = right;
+			hashes[left_index] = left;
+
+			next_node_index -= 1;
+			queue.push_front((next_node_index, hash_node(&left, &right)));
+		}
+
+		if let Some((node_index, root)) = queue.pop_front() {
+			hashes[node_index] = root;
+		}
+
+		Self { leaves, hashes }
+	}
+
+	fn root(&self) -> Hash {
+		self.hashes.first().copied().unwrap_or_default()
+	}
+
+	/// Build a proof for the leaves at `leaf_indices`.
+	fn build_proof(&self, leaf_indices: &BTreeSet<usize>) -> Proof {
+		if self.leaves.is_empty() {
+			return Proof { leaves: Vec::new(), leaf_indices: Vec::new(), nodes: Vec::new() };
+		}
+
+		let num_inner_nodes = self.leaves.len() as u64 - 1;
+		let mut leaves =
+			leaf_indices.iter().map(|i| num_inner_nodes + *i as u64).collect::<Vec<_>>();
+		leaves.sort_by_key(|l| leaf_order(*l));
+
+		Proof {
+			leaves: leaves
+				.iter()
+				.map(|l| self.leaves[(l - num_inner_nodes) as usize].clone())
+				.collect(),
+			leaf_indices: leaves.iter().map(|l| *l as u32).collect(),
+			nodes: required_nodes(&leaves).into_iter().map(|n| self.hashes[n as usize]).collect(),
+		}
+	}
+}
+
+/// Calculate the root of `proof`.
+///
+/// Puts all the known hashes into a map and then calculates the ancestors of the leaves, from the
+/// bottom to the top.
+fn proof_root(proof: &Proof) -> Result<Hash, String> {
+	if proof.leaves.len() != proof.leaf_indices.len() {
+		return Err("Number of leaves and leaf indices differ".into());
+	}
+
+	if proof.leaves.is_empty() && proof.nodes.is_empty() {
+		return Ok(Hash::default());
+	}
+
+	let leaves = proof.leaf_indices.iter().map(|l| u64::from(*l)).collect::<Vec<_>>();
+
+	if !leaves.windows(2).all(|w| leaf_order(w[0]) < leaf_order(w[1])) {
+		return Err("Leaves are not sorted".into());
+	}
+
+	let ancestors = ancestors(&leaves);
+	if leaves.iter().any(|l| ancestors.contains(l)) {
+		return Err("A leaf is the ancestor of another leaf".into());
+	}
+
+	let required = required_nodes(&leaves);
+	if required.len() != proof.nodes.len() {
+		return Err("Wrong number of node hashes".into());
+	}
+
+	let mut known = leaves
+		.iter()
+		.zip(&proof.leaves)
+		.map(|(l, leaf)| (*l, hash_leaf(leaf)))
+		.chain(required.into_iter().zip(proof.nodes.iter().copied()))
+		.collect::<BTreeMap<_, _>>();
+
+	// The children always have a higher index than their parent.
+	for node_index in ancestors.into_iter().rev() {
+		let left = known[&(node_index * 2 + 1)];
+		let right = known[&(node_index * 2 + 2)];
+		known.insert(node_index, hash_node(&left, &right));
+	}
+
+	Ok(known[&0])
+}
+
+#[test]
+fn reference_matches_fixtures() {
+	for (fixture, _) in FIXTURES {
+		let metadata = load_metadata(fixture);
+		let type_information = generate_type_information(&metadata).unwrap();
+		let type_ids = type_information.types.keys().copied().collect::<Vec<_>>();
+
+		let merkle_tree = MerkleTree::new(type_information.types.clone());
+		let reference = ReferenceTree::new(type_information.types.into_values().collect());
+
+		assert_eq!(reference.hashes, merkle_tree.hashes, "{fixture}");
+		assert_eq!(reference.root(), merkle_tree.root(), "{fixture}");
+
+		for step in [1, 2, 3, 10, 97, 1000, type_ids.len()] {
+			let leaf_indices = (0..type_ids.len()).step_by(step).collect::<BTreeSet<_>>();
+
+			let proof = merkle_tree.build_proof(leaf_indices.iter().map(|i| type_ids[*i])).unwrap();
+			assert_eq!(reference.build_proof(&leaf_indices), proof, "{fixture} with step {step}");
+			assert_eq!(Ok(reference.root()), proof_root(&proof), "{fixture} with step {step}");
+			assert_eq!(Ok(reference.root()), proof.root(), "{fixture} with step {step}");
+		}
+	}
+}
+
+#[test]
+fn reference_matches_extrinsic_proof() {
+	let metadata = load_metadata("rococo_metadata_v15");
+	let type_information = generate_type_information(&metadata).unwrap();
+	let type_ids = type_information.types.keys().copied().collect::<Vec<_>>();
+	let reference = ReferenceTree::new(type_information.types.into_values().collect());
+
+	let proof = crate::generate_proof_for_extrinsic(
+		&array_bytes::hex2bytes(TEST_EXT).unwrap(),
+		Some(&array_bytes::hex2bytes(TEST_ADDITIONAL_SIGNED).unwrap()),
+		&metadata,
+	)
+	.unwrap();
+
+	let leaf_indices = proof
+		.leaves
+		.iter()
+		.map(|leaf| {
+			let type_id = match leaf.type_def.as_enumeration() {
+				Some(v) => TypeId::Enumeration { type_id: leaf.type_id.0, variant: v.index.0 },
+				None => TypeId::Other(leaf.type_id.0),
+			};
+			type_ids.binary_search(&type_id).unwrap()
+		})
+		.collect::<BTreeSet<_>>();
+
+	assert_eq!(reference.build_proof(&leaf_indices), proof);
+	assert_eq!(Ok(reference.root()), proof_root(&proof));
+}
+
+proptest::proptest! {
+	#![proptest_config(ProptestConfig::with_cases(64))]
+
+	#[test]
+	fn reference_matches_random_trees((num_leaves, leaves) in tree_and_leaves()) {
+		let merkle_tree =
+			MerkleTree::new((0..num_leaves).map(|n| (TypeId::Other(n), test_type(n, 1))));
+		let reference = ReferenceTree::new((0..num_leaves).map(|n| test_type(n, 1)).collect());
+		let root = reference.root();
+
+		prop_assert_eq!(&reference.hashes, &merkle_tree.hashes);
+
+		let leaf_indices = leaves.iter().map(|l| *l as usize).collect::<BTreeSet<_>>();
+		let proof = merkle_tree.build_proof(leaves.iter().map(|l| TypeId::Other(*l))).unwrap();
+		prop_assert_eq!(&reference.build_proof(&leaf_indices), &proof);
+		prop_assert_eq!(Ok(root), proof_root(&proof));
+
+		// Both implementations need to reject altered proofs.
+		let rejected = |proof: &Proof| proof_root(proof) != Ok(root) && proof.root() != Ok(root);
+
+		for i in 0..proof.nodes.len() {
+			let mut altered = proof.clone();
+			altered.nodes[i][i % 32] ^= 1;
+			prop_assert!(rejected(&altered), "Accepted altered node {}", i);
+
+			let mut removed = proof.clone();
+			removed.nodes.remove(i);
+			prop_assert!(rejected(&removed), "Accepted removed node {}", i);
+		}
+
+		for i in 0..proof.leaves.len() {
+			let mut altered = proof.clone();
+			altered.leaf_indices[i] ^= 1;
+			prop_assert!(rejected(&altered), "Accepted altered leaf index {}", i);
+
+			let mut removed = proof.clone();
+			removed.leaves.remove(i);
+			removed.leaf_indices.remove(i);
+			prop_assert!(rejected(&removed), "Accepted removed leaf {}", i);
+		}
+	}
+}
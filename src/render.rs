@@ -0,0 +1,515 @@
+//! Rendering of decoded values for humans.
+
+use alloc::{
+	boxed::Box,
+	collections::BTreeMap,
+	format,
+	string::{String, ToString},
+	vec::Vec,
+};
+use core::ops::RangeInclusive;
+
+use crate::{
+	types::{Field, Type, TypeDef, TypeRef},
+	value::Value,
+};
+
+/// Renders values of a specific type.
+///
+/// Implemented for closures with the same signature as [`Renderer::render`].
+pub trait Renderer {
+	/// Render `value` of the type `ty`.
+	///
+	/// For enumerations `ty` is the type of the variant of `value`. It is `None` for primitive
+	/// types. Nested values can be rendered using `context`. Returns `None` if the renderer can't
+	/// render `value`, then it is rendered without a renderer.
+	fn render(&self, value: &Value, ty: Option<&Type>, context: &RenderContext) -> Option<String>;
+}
+
+impl<F> Renderer for F
+where
+	F: Fn(&Value, Option<&Type>, &RenderContext) -> Option<String>,
+{
+	fn render(&self, value: &Value, ty: Option<&Type>, context: &RenderContext) -> Option<String> {
+		self(value, ty, context)
+	}
+}
+
+/// The renderers by [`Type::path`] and by [`Field::type_name`].
+///
+/// The [`Default`] contains renderers for common `sp_*` and `frame_*` types:
+///
+/// - `Perbill`, `Permill`, `Percent` and `Perquintill` as a percentage.
+/// - `FixedU128`, `FixedI128`, `FixedU64` and `FixedI64` as a decimal.
+/// - `H160`, `H256`, `H512`, `AccountId32` and `AccountId20` as hex.
+/// - `MultiAddress` as the address it contains.
+/// - `Option<T>` as `None` or the rendered value.
+/// - Fields with the type name `Vec<u8>` or `Bytes` as text, if they are valid UTF-8, or as hex.
+pub struct Renderers {
+	by_path: BTreeMap<String, Box<dyn Renderer>>,
+	by_type_name: BTreeMap<String, Box<dyn Renderer>>,
+}
+
+impl Default for Renderers {
+	fn default() -> Self {
+		let mut renderers = Self::empty();
+
+		for (name, decimals) in
+			[("Perbill", 7), ("Permill", 4), ("Percent", 0), ("Perquintill", 16)]
+		{
+			renderers.register_path(
+				&format!("sp_arithmetic::per_things::{name}"),
+				move |value: &Value, _: Option<&Type>, _: &RenderContext| {
+					Some(format!("{}%", decimal(value.inner(), decimals)?))
+				},
+			);
+		}
+
+		for (name, decimals) in
+			[("FixedU128", 18), ("FixedI128", 18), ("FixedU64", 9), ("FixedI64", 9)]
+		{
+			renderers.register_path(
+				&format!("sp_arithmetic::fixed_point::{name}"),
+				move |value: &Value, _: Option<&Type>, _: &RenderContext| {
+					decimal(value.inner(), decimals)
+				},
+			);
+		}
+
+		for path in [
+			"primitive_types::H160",
+			"primitive_types::H256",
+			"primitive_types::H512",
+			"sp_core::crypto::AccountId32",
+			"account::AccountId20",
+		] {
+			renderers.register_path(path, render_hex);
+		}
+
+		renderers.register_path("sp_runtime::multiaddress::MultiAddress", render_multi_address);
+		renderers.register_path("Option", render_option);
+
+		for type_name in ["Vec<u8>", "sp_std::vec::Vec<u8>", "Bytes"] {
+			renderers.register_type_name(type_name, render_bytes);
+		}
+
+		renderers
+	}
+}
+
+impl Renderers {
+	/// Returns an instance without any renderer.
+	pub fn empty() -> Self {
+		Self { by_path: Default::default(), by_type_name: Default::default() }
+	}
+
+	/// Register the `renderer` for the types with the given `path`.
+	///
+	/// The elements of the `path` are joined by `::`, e.g. `sp_arithmetic::per_things::Perbill`.
+	/// Overwrites an already registered renderer for the same `path`.
+	pub fn register_path(&mut self, path: &str, renderer: impl Renderer + 'static) {
+		self.by_path.insert(path.into(), Box::new(renderer));
+	}
+
+	/// Register the `renderer` for the fields with the given `type_name`.
+	///
+	/// The `type_name` needs to match exactly, e.g. `Vec<u8>`. Renderers by type name take
+	/// precedence over the renderers by path. Overwrites an already registered renderer for the
+	/// same `type_name`.
+	pub fn register_type_name(&mut self, type_name: &str, renderer: impl Renderer + 'static) {
+		self.by_type_name.insert(type_name.into(), Box::new(renderer));
+	}
+
+	/// Render the `value` of the type `type_ref`.
+	///
+	/// `types` need to contain all types of `value`, e.g. the leaves of the proof that was used to
+	/// decode it.
+	pub fn render<'a>(
+		&self,
+		value: &Value,
+		type_ref: TypeRef,
+		types: impl IntoIterator<Item = &'a Type>,
+	) -> String {
+		let types = types.into_iter().fold(BTreeMap::<_, Vec<_>>::new(), |mut map, ty| {
+			map.entry(ty.type_id.0).or_default().push(ty);
+			map
+		});
+
+		RenderContext { renderers: self, types }.render(value, type_ref)
+	}
+}
+
+/// The context for rendering nested values.
+pub struct RenderContext<'a> {
+	renderers: &'a Renderers,
+	types: BTreeMap<u32, Vec<&'a Type>>,
+}
+
+impl RenderContext<'_> {
+	/// Render the `value` of the type `type_ref`.
+	pub fn render(&self, value: &Value, type_ref: TypeRef) -> String {
+		let ty = self.resolve(value, type_ref);
+
+		ty.and_then(|ty| self.renderers.by_path.get(&ty.path.join("::")))
+			.and_then(|renderer| renderer.render(value, ty, self))
+			.unwrap_or_else(|| self.render_plain(value, ty))
+	}
+
+	/// Render the `value` of the given `field`.
+	pub fn render_field(&self, value: &Value, field: &Field) -> String {
+		field
+			.type_name
+			.as_ref()
+			.and_then(|type_name| self.renderers.by_type_name.get(type_name))
+			.and_then(|renderer| renderer.render(value, self.resolve(value, field.ty), self))
+			.unwrap_or_else(|| self.render(value, field.ty))
+	}
+
+	/// Returns the type of `value`.
+	fn resolve(&self, value: &Value, type_ref: TypeRef) -> Option<&Type> {
+		let types = self.types.get(&type_ref.id()?)?;
+
+		match value {
+			Value::Variant { index, .. } => types.iter().copied().find(|ty| {
+				ty.type_def.as_enumeration().is_some_and(|v| v.index.0 == u32::from(*index))
+			}),
+			_ => types.first().copied(),
+		}
+	}
+
+	/// Render `value` without a renderer.
+	fn render_plain(&self, value: &Value, ty: Option<&Type>) -> String {
+		match (value, ty.map(|ty| &ty.type_def)) {
+			(Value::Composite(values), Some(TypeDef::Composite(fields))) =>
+				self.render_fields(None, values, fields),
+			(Value::Variant { name, fields: values, .. }, Some(TypeDef::Enumeration(variant))) =>
+				self.render_fields(Some(name), values, &variant.fields),
+			(Value::Sequence(values), Some(TypeDef::Sequence(type_ref))) =>
+				self.render_sequence(values, *type_ref),
+			(Value::Sequence(values), Some(TypeDef::Array(array))) =>
+				self.render_sequence(values, array.type_param),
+			(Value::Sequence(values), Some(TypeDef::Tuple(type_refs)))
+				if values.len() == type_refs.len() =>
+				format!("({})", join(values.iter().zip(type_refs).map(|(v, t)| self.render(v, *t)))),
+			_ => render_untyped(value),
+		}
+	}
+
+	fn render_fields(
+		&self,
+		name: Option<&str>,
+		values: &[(Option<String>, Value)],
+		fields: &[Field],
+	) -> String {
+		if values.len() != fields.len() {
+			return render_untyped(&Value::Composite(values.to_vec()))
+		}
+
+		let rendered = values.iter().zip(fields).map(|((_, v), f)| self.render_field(v, f));
+
+		format_fields(name, values, rendered)
+	}
+
+	fn render_sequence(&self, values: &[Value], type_ref: TypeRef) -> String {
+		if type_ref == TypeRef::U8 {
+			if let Some(bytes) = Value::Sequence(values.to_vec()).as_bytes() {
+				return array_bytes::bytes2hex("0x", bytes)
+			}
+		}
+
+		format!("[{}]", join(values.iter().map(|v| self.render(v, type_ref))))
+	}
+}
+
+/// Render `value` without type information.
+fn render_untyped(value: &Value) -> String {
+	match value {
+		Value::Bool(v) => v.to_string(),
+		Value::Char(v) => format!("{v:?}"),
+		Value::Str(v) => format!("{v:?}"),
+		Value::Unsigned(v) => v.to_string(),
+		Value::Signed(v) => v.to_string(),
+		Value::U256(v) | Value::I256(v) => {
+			let mut be = *v;
+			be.reverse();
+			array_bytes::bytes2hex("0x", be)
+		},
+		Value::Composite(values) =>
+			format_fields(None, values, values.iter().map(|(_, v)| render_untyped(v))),
+		Value::Variant { name, fields, .. } =>
+			format_fields(Some(name), fields, fields.iter().map(|(_, v)| render_untyped(v))),
+		Value::Sequence(values) => match value.as_bytes() {
+			Some(bytes) if !bytes.is_empty() => array_bytes::bytes2hex("0x", bytes),
+			_ => format!("[{}]", join(values.iter().map(render_untyped))),
+		},
+		Value::BitSequence(bits) =>
+			format!("0b{}", bits.iter().map(|b| if *b { '1' } else { '0' }).collect::<String>()),
+	}
+}
+
+/// Format the already `rendered` fields.
+///
+/// Composites with exactly one unnamed field are rendered as the field.
+fn format_fields(
+	name: Option<&str>,
+	values: &[(Option<String>, Value)],
+	rendered: impl Iterator<Item = String>,
+) -> String {
+	let named = values.iter().all(|(n, _)| n.is_some());
+	let fields = values.iter().zip(rendered).map(|((n, _), r)| match n {
+		Some(n) => format!("{n}: {r}"),
+		None => r,
+	});
+
+	match (name, values.len(), named) {
+		(Some(name), 0, _) => name.into(),
+		(None, 0, _) => "()".into(),
+		(None, 1, false) => join(fields),
+		(name, _, true) =>
+			format!("{}{{ {} }}", name.map_or(String::new(), |n| format!("{n} ")), join(fields)),
+		(name, _, false) => format!("{}({})", name.unwrap_or_default(), join(fields)),
+	}
+}
+
+fn join(items: impl Iterator<Item = String>) -> String {
+	items.collect::<Vec<_>>().join(", ")
+}
+
+/// Render the integer `value` as a decimal with `decimals` decimal places.
+///
+/// Trailing zeros of the decimal places are removed.
+fn decimal(value: &Value, decimals: u32) -> Option<String> {
+	let (negative, value) = match value {
+		Value::Unsigned(v) => (false, *v),
+		Value::Signed(v) => (v.is_negative(), v.unsigned_abs()),
+		_ => return None,
+	};
+
+	let unit = 10u128.pow(decimals);
+	let fraction = format!("{:0width$}", value % unit, width = decimals as usize);
+	let fraction = fraction.trim_end_matches('0');
+	let sign = if negative { "-" } else { "" };
+
+	Some(if fraction.is_empty() {
+		format!("{sign}{}", value / unit)
+	} else {
+		format!("{sign}{}.{fraction}", value / unit)
+	})
+}
+
+fn render_hex(value: &Value, _: Option<&Type>, _: &RenderContext) -> Option<String> {
+	Some(array_bytes::bytes2hex("0x", value.inner().as_bytes()?))
+}
+
+/// Renders the bytes as text, if they are UTF-8 and every character is printable.
+///
+/// Otherwise the bytes are rendered as hex, so that e.g. bidi overrides or zero-width characters
+/// can't change how the rest of the call is displayed.
+fn render_bytes(value: &Value, _: Option<&Type>, _: &RenderContext) -> Option<String> {
+	let bytes = value.inner().as_bytes()?;
+
+	match core::str::from_utf8(&bytes) {
+		Ok(text) if text.chars().all(is_printable) => Some(text.into()),
+		_ => Some(array_bytes::bytes2hex("0x", bytes)),
+	}
+}
+
+/// The format characters (Unicode category `Cf`).
+const FORMAT_CHARACTERS: &[RangeInclusive<char>] = &[
+	'\u{ad}'..='\u{ad}',
+	'\u{600}'..='\u{605}',
+	'\u{61c}'..='\u{61c}',
+	'\u{6dd}'..='\u{6dd}',
+	'\u{70f}'..='\u{70f}',
+	'\u{890}'..='\u{891}',
+	'\u{8e2}'..='\u{8e2}',
+	'\u{180e}'..='\u{180e}',
+	'\u{200b}'..='\u{200f}',
+	'\u{202a}'..='\u{202e}',
+	'\u{2060}'..='\u{2064}',
+	'\u{2066}'..='\u{206f}',
+	'\u{feff}'..='\u{feff}',
+	'\u{fff9}'..='\u{fffb}',
+	'\u{110bd}'..='\u{110bd}',
+	'\u{110cd}'..='\u{110cd}',
+	'\u{13430}'..='\u{1343f}',
+	'\u{1bca0}'..='\u{1bca3}',
+	'\u{1d173}'..='\u{1d17a}',
+	'\u{e0001}'..='\u{e0001}',
+	'\u{e0020}'..='\u{e007f}',
+];
+
+/// Returns `true` if `c` is printable.
+///
+/// Control characters, format characters and whitespace apart from the space are not printable.
+fn is_printable(c: char) -> bool {
+	!c.is_control() &&
+		(c == ' ' || !c.is_whitespace()) &&
+		!FORMAT_CHARACTERS.iter().any(|range| range.contains(&c))
+}
+
+fn render_multi_address(
+	value: &Value,
+	ty: Option<&Type>,
+	context: &RenderContext,
+) -> Option<String> {
+	let variant = ty?.type_def.as_enumeration()?;
+	let [(_, inner)] = &value.fields()?[..] else { return None };
+
+	match value.variant_name()? {
+		"Id" | "Index" => Some(context.render_field(inner, variant.fields.first()?)),
+		_ => Some(array_bytes::bytes2hex("0x", inner.inner().as_bytes()?)),
+	}
+}
+
+fn render_option(value: &Value, ty: Option<&Type>, context: &RenderContext) -> Option<String> {
+	let variant = ty?.type_def.as_enumeration()?;
+
+	match (value.variant_name()?, &value.fields()?[..]) {
+		("None", []) => Some("None".into()),
+		("Some", [(_, inner)]) => Some(context.render_field(inner, variant.fields.first()?)),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		decode_extrinsic, generate_proof_for_extrinsic, generate_type_information,
+		tests::{call_index, load_metadata},
+	};
+	use alloc::vec;
+	use codec::{Compact, Encode};
+
+	/// Decode the unsigned extrinsic with the given `call` and render the call.
+	fn render_call(renderers: &Renderers, call: impl Encode) -> String {
+		let metadata = load_metadata("rococo_metadata_v15");
+		let extrinsic_metadata = generate_type_information(&metadata).unwrap().extrinsic_metadata;
+
+		let extrinsic = (4u8, call).encode().encode();
+		let proof = generate_proof_for_extrinsic(&extrinsic, None, &metadata).unwrap();
+		let decoded = decode_extrinsic(&extrinsic, None, &extrinsic_metadata, &proof).unwrap();
+
+		renderers.render(&decoded.call, extrinsic_metadata.call_ty, &proof.leaves)
+	}
+
+	#[test]
+	fn render_calls_works() {
+		let metadata = load_metadata("rococo_metadata_v15");
+		let type_information = generate_type_information(&metadata).unwrap();
+		let call_index = |pallet, call| call_index(&type_information, pallet, call);
+		let renderers = Renderers::default();
+
+		let transfer =
+			(call_index("Balances", "transfer_keep_alive"), 0u8, [1u8; 32], Compact(10u128));
+		assert_eq!(
+			format!(
+				"Balances(transfer_keep_alive {{ dest: {}, value: 10 }})",
+				array_bytes::bytes2hex("0x", [1u8; 32])
+			),
+			render_call(&renderers, transfer)
+		);
+		assert_eq!(
+			format!(
+				"Balances(transfer_keep_alive {{ dest: Id({}), value: 10 }})",
+				array_bytes::bytes2hex("0x", [1u8; 32])
+			),
+			render_call(&Renderers::empty(), transfer)
+		);
+
+		let remark = |data: &[u8]| (call_index("System", "remark"), data.to_vec());
+		assert_eq!("System(remark { remark: RFC78 })", render_call(&renderers, remark(b"RFC78")));
+		assert_eq!("System(remark { remark: 0x00ff })", render_call(&renderers, remark(&[0, 255])));
+		assert_eq!(
+			"System(remark { remark: hello world })",
+			render_call(&renderers, remark(b"hello world"))
+		);
+		// Text with non-printable or format characters is rendered as hex.
+		for text in ["a\u{202e}b", "a\u{200b}b", "a\u{feff}b", "a\nb", "a\u{2028}b", "a\u{7f}b"] {
+			assert_eq!(
+				format!("System(remark {{ remark: {} }})", array_bytes::bytes2hex("0x", text)),
+				render_call(&renderers, remark(text.as_bytes()))
+			);
+		}
+
+		let proxy = |proxy_type: Option<u8>| {
+			(call_index("Proxy", "proxy"), 0u8, [2u8; 32], proxy_type, remark(b"hello"))
+		};
+		assert_eq!(
+			format!(
+				"Proxy(proxy {{ real: {}, force_proxy_type: None, call: System(remark {{ remark: \
+				 hello }}) }})",
+				array_bytes::bytes2hex("0x", [2u8; 32])
+			),
+			render_call(&renderers, proxy(None))
+		);
+		assert!(render_call(&renderers, proxy(Some(0))).contains("force_proxy_type: Any,"));
+
+		// Custom renderers take precedence over the default ones.
+		let mut renderers = Renderers::default();
+		renderers.register_type_name(
+			"T::Balance",
+			|value: &Value, _: Option<&Type>, _: &RenderContext| {
+				Some(format!("{} ROC", decimal(value, 12)?))
+			},
+		);
+		renderers.register_path(
+			"sp_core::crypto::AccountId32",
+			|_: &Value, _: Option<&Type>, _: &RenderContext| Some("Alice".into()),
+		);
+		let transfer = (
+			call_index("Balances", "transfer_keep_alive"),
+			0u8,
+			[1u8; 32],
+			Compact(1_500_000_000_000u128),
+		);
+		assert_eq!(
+			"Balances(transfer_keep_alive { dest: Alice, value: 1.5 ROC })",
+			render_call(&renderers, transfer)
+		);
+	}
+
+	#[test]
+	fn render_arithmetic_works() {
+		let ty = |path: &str, type_ref| Type {
+			path: path.split("::").map(Into::into).collect(),
+			type_def: TypeDef::Composite(vec![Field { name: None, ty: type_ref, type_name: None }]),
+			type_id: 0u32.into(),
+		};
+		let render = |path, type_ref, value| {
+			Renderers::default().render(
+				&Value::Composite(vec![(None, value)]),
+				TypeRef::ById(0u32.into()),
+				&[ty(path, type_ref)],
+			)
+		};
+
+		assert_eq!(
+			"12.5%",
+			render(
+				"sp_arithmetic::per_things::Perbill",
+				TypeRef::U32,
+				Value::Unsigned(125_000_000)
+			)
+		);
+		assert_eq!(
+			"100%",
+			render("sp_arithmetic::per_things::Percent", TypeRef::U8, Value::Unsigned(100))
+		);
+		assert_eq!(
+			"1.5",
+			render(
+				"sp_arithmetic::fixed_point::FixedU128",
+				TypeRef::U128,
+				Value::Unsigned(1_500_000_000_000_000_000)
+			)
+		);
+		assert_eq!(
+			"-0.000000001",
+			render("sp_arithmetic::fixed_point::FixedI64", TypeRef::I64, Value::Signed(-1))
+		);
+		// Unknown types are rendered as their single field.
+		assert_eq!("7", render("some::Wrapper", TypeRef::U32, Value::Unsigned(7)));
+	}
+}
@@ -0,0 +1,338 @@
+//! Verification of the signatures of decoded extrinsics.
+
+use alloc::{format, string::String};
+use blake2::{digest::consts::U32, Blake2b, Digest};
+use sha3::Keccak256;
+
+use crate::value::Value;
+
+/// The signing context used by Substrate for `sr25519` signatures.
+const SR25519_SIGNING_CONTEXT: &[u8] = b"substrate";
+
+/// Payloads longer than this are hashed with `blake2_256` before signing.
+const MAX_UNHASHED_PAYLOAD_LEN: usize = 256;
+
+/// The signature scheme that was used to sign an extrinsic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureScheme {
+	Sr25519,
+	Ed25519,
+	/// `secp256k1` signature, the account id is the `blake2_256` hash of the compressed public
+	/// key.
+	Ecdsa,
+	/// `secp256k1` signature over the `keccak_256` hash, the account id is the Ethereum address.
+	Ethereum,
+}
+
+/// Verify the `signature` of `address` over the signing `payload`.
+///
+/// `address` and `signature` are the values decoded from the `address_ty` and `signature_ty` of
+/// the extrinsic, e.g. by [`decode_extrinsic`](crate::decode_extrinsic). Supported are the
+/// variants of `MultiSignature` for an `AccountId32` address and Ethereum-style signatures for
+/// an `AccountId20` address. `payload` is the unhashed signing payload, see
+/// [`DecodedExtrinsic::signing_payload`](crate::DecodedExtrinsic::signing_payload). If it is
+/// longer than 256 bytes, its `blake2_256` hash is verified instead.
+///
+/// Returns the signature scheme that was used.
+pub fn verify_signature(
+	address: &Value,
+	signature: &Value,
+	payload: &[u8],
+) -> Result<SignatureScheme, String> {
+	let hashed_payload;
+	let payload = if payload.len() > MAX_UNHASHED_PAYLOAD_LEN {
+		hashed_payload = blake2_256(payload);
+		&hashed_payload[..]
+	} else {
+		payload
+	};
+
+	let account = address.as_account_id().ok_or_else(|| String::from("Unsupported address"))?;
+
+	let (scheme, signature) = match signature {
+		Value::Variant { name, fields, .. } => {
+			let scheme = match name.as_str() {
+				"Sr25519" => SignatureScheme::Sr25519,
+				"Ed25519" => SignatureScheme::Ed25519,
+				"Ecdsa" => SignatureScheme::Ecdsa,
+				n => return Err(format!("Unsupported signature scheme: {n}")),
+			};
+
+			let signature = match &fields[..] {
+				[(_, value)] => value.inner().as_bytes(),
+				_ => None,
+			};
+
+			(scheme, signature)
+		},
+		// `AccountId20` and `EthereumSignature`.
+		v => match v.inner().as_bytes() {
+			Some(signature) if account.len() == 20 && signature.len() == 65 =>
+				(SignatureScheme::Ethereum, Some(signature)),
+			_ => return Err("Unsupported signature scheme".into()),
+		},
+	};
+	let signature = signature.ok_or_else(|| String::from("Signature is not a byte array"))?;
+
+	let valid = match scheme {
+		SignatureScheme::Sr25519 => verify_sr25519(&account, &signature, payload)?,
+		SignatureScheme::Ed25519 => verify_ed25519(&account, &signature, payload)?,
+		SignatureScheme::Ecdsa => {
+			let public = recover_secp256k1(&signature, &blake2_256(payload))?;
+
+			account[..] == blake2_256(public.to_encoded_point(true).as_bytes())
+		},
+		SignatureScheme::Ethereum => {
+			let public = recover_secp256k1(&signature, &Keccak256::digest(payload).into())?;
+
+			account[..] == ethereum_address(&public)
+		},
+	};
+
+	if valid {
+		Ok(scheme)
+	} else {
+		Err(format!("Invalid {scheme:?} signature"))
+	}
+}
+
+fn verify_sr25519(account: &[u8], signature: &[u8], payload: &[u8]) -> Result<bool, String> {
+	let public = schnorrkel::PublicKey::from_bytes(account)
+		.map_err(|e| format!("Invalid sr25519 public key: {e}"))?;
+	let signature = schnorrkel::Signature::from_bytes(signature)
+		.map_err(|e| format!("Invalid sr25519 signature: {e}"))?;
+
+	Ok(public.verify_simple(SR25519_SIGNING_CONTEXT, payload, &signature).is_ok())
+}
+
+fn verify_ed25519(account: &[u8], signature: &[u8], payload: &[u8]) -> Result<bool, String> {
+	use ed25519_dalek::Verifier;
+
+	let public = account
+		.try_into()
+		.ok()
+		.and_then(|a| ed25519_dalek::VerifyingKey::from_bytes(a).ok())
+		.ok_or_else(|| String::from("Invalid ed25519 public key"))?;
+	let signature = ed25519_dalek::Signature::from_slice(signature)
+		.map_err(|e| format!("Invalid ed25519 signature: {e}"))?;
+
+	Ok(public.verify(payload, &signature).is_ok())
+}
+
+/// Recover the public key from the 65 byte recoverable `signature` over `hash`.
+///
+/// The recovery id is accepted in Ethereum style (`27`/`28`) as well. Signatures with a high `s`
+/// are normalized, as Substrate accepts them.
+fn recover_secp256k1(
+	signature: &[u8],
+	hash: &[u8; 32],
+) -> Result<k256::ecdsa::VerifyingKey, String> {
+	use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+
+	let [signature @ .., v] = signature else {
+		return Err("Invalid secp256k1 signature length".into());
+	};
+	if signature.len() != 64 {
+		return Err("Invalid secp256k1 signature length".into());
+	}
+
+	let v = if *v >= 27 { v - 27 } else { *v };
+	let mut recovery_id =
+		RecoveryId::from_byte(v).ok_or_else(|| format!("Invalid recovery id: {v}"))?;
+	let mut signature = Signature::from_slice(signature)
+		.map_err(|e| format!("Invalid secp256k1 signature: {e}"))?;
+
+	if let Some(normalized) = signature.normalize_s() {
+		signature = normalized;
+		recovery_id = RecoveryId::new(!recovery_id.is_y_odd(), recovery_id.is_x_reduced());
+	}
+
+	VerifyingKey::recover_from_prehash(hash, &signature, recovery_id)
+		.map_err(|e| format!("Failed to recover secp256k1 public key: {e}"))
+}
+
+/// Returns the Ethereum address of `public`.
+fn ethereum_address(public: &k256::ecdsa::VerifyingKey) -> [u8; 20] {
+	let hash = Keccak256::digest(&public.to_encoded_point(false).as_bytes()[1..]);
+
+	hash[12..].try_into().expect("Keccak256 returns 32 bytes; qed")
+}
+
+fn blake2_256(data: &[u8]) -> [u8; 32] {
+	Blake2b::<U32>::digest(data).into()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		decode_extrinsic, generate_proof_for_extrinsic, generate_type_information,
+		merkle_tree::tests::{TEST_ADDITIONAL_SIGNED, TEST_EXT},
+		tests::load_metadata,
+		DecodedExtrinsic,
+	};
+	use codec::Encode;
+	use k256::ecdsa::SigningKey;
+
+	/// Assemble a signed extrinsic and decode it with a proof for the extrinsic.
+	fn decode_signed(
+		fixture: &str,
+		address: &[u8],
+		signature: &[u8],
+		extra: &[u8],
+		call: &[u8],
+		additional_signed: &[u8],
+	) -> DecodedExtrinsic {
+		let metadata = load_metadata(fixture);
+		let extrinsic_metadata = generate_type_information(&metadata).unwrap().extrinsic_metadata;
+
+		let extrinsic = [&[0b1000_0100][..], address, signature, extra, call].concat().encode();
+		let proof =
+			generate_proof_for_extrinsic(&extrinsic, Some(additional_signed), &metadata).unwrap();
+
+		decode_extrinsic(&extrinsic, Some(additional_signed), &extrinsic_metadata, &proof).unwrap()
+	}
+
+	fn verify(decoded: &DecodedExtrinsic) -> Result<SignatureScheme, String> {
+		verify_signature(
+			decoded.address.as_ref().unwrap(),
+			decoded.signature.as_ref().unwrap(),
+			decoded.signing_payload().unwrap(),
+		)
+	}
+
+	fn sign_secp256k1(key: &SigningKey, hash: &[u8]) -> Vec<u8> {
+		let (signature, recovery_id) = key.sign_prehash_recoverable(hash).unwrap();
+
+		[&signature.to_bytes()[..], &[recovery_id.to_byte()]].concat()
+	}
+
+	#[test]
+	fn multi_signature_works() {
+		let additional_signed = array_bytes::hex2bytes(TEST_ADDITIONAL_SIGNED).unwrap();
+		// Spec version, transaction version, genesis hash and the hash of the birth block.
+		assert_eq!(72, additional_signed.len());
+
+		// Take the values of the signed extensions from the test extrinsic.
+		let metadata = load_metadata("rococo_metadata_v15");
+		let extrinsic_metadata = generate_type_information(&metadata).unwrap().extrinsic_metadata;
+		let extrinsic = array_bytes::hex2bytes(TEST_EXT).unwrap();
+		let proof =
+			generate_proof_for_extrinsic(&extrinsic, Some(&additional_signed), &metadata).unwrap();
+		let payload =
+			decode_extrinsic(&extrinsic, Some(&additional_signed), &extrinsic_metadata, &proof)
+				.unwrap()
+				.signing_payload()
+				.unwrap()
+				.to_vec();
+		assert!(payload.len() <= MAX_UNHASHED_PAYLOAD_LEN);
+		assert_eq!(additional_signed, payload[payload.len() - additional_signed.len()..]);
+		// Mortal era, nonce and tip of `0`.
+		let extra = [7, 0, 0, 0];
+		let call = &payload[..payload.len() - additional_signed.len() - extra.len()];
+		assert_eq!(extra, payload[call.len()..][..extra.len()]);
+
+		let sr25519 = schnorrkel::MiniSecretKey::from_bytes(&[1; 32])
+			.unwrap()
+			.expand_to_keypair(schnorrkel::ExpansionMode::Ed25519);
+		let ed25519 = ed25519_dalek::SigningKey::from_bytes(&[2; 32]);
+		let ecdsa = SigningKey::from_slice(&[3; 32]).unwrap();
+
+		let signers: [(SignatureScheme, u8, Vec<u8>, Vec<u8>); 3] = [
+			(
+				SignatureScheme::Ed25519,
+				0,
+				ed25519.verifying_key().to_bytes().to_vec(),
+				ed25519_dalek::Signer::sign(&ed25519, &payload).to_bytes().to_vec(),
+			),
+			(
+				SignatureScheme::Sr25519,
+				1,
+				sr25519.public.to_bytes().to_vec(),
+				sr25519.sign_simple(SR25519_SIGNING_CONTEXT, &payload).to_bytes().to_vec(),
+			),
+			(
+				SignatureScheme::Ecdsa,
+				2,
+				blake2_256(ecdsa.verifying_key().to_encoded_point(true).as_bytes()).to_vec(),
+				sign_secp256k1(&ecdsa, &blake2_256(&payload)),
+			),
+		];
+
+		for (scheme, index, account, signature) in signers {
+			// `MultiAddress::Id` and the `MultiSignature` variant.
+			let address = [&[0][..], &account].concat();
+			let signature = [&[index][..], &signature].concat();
+
+			let decoded = decode_signed(
+				"rococo_metadata_v15",
+				&address,
+				&signature,
+				&extra,
+				call,
+				&additional_signed,
+			);
+			assert_eq!(Ok(scheme), verify(&decoded));
+
+			// A different payload, e.g. a different genesis hash.
+			let mut other_additional_signed = additional_signed.clone();
+			*other_additional_signed.last_mut().unwrap() = 1;
+			let decoded = decode_signed(
+				"rococo_metadata_v15",
+				&address,
+				&signature,
+				&extra,
+				call,
+				&other_additional_signed,
+			);
+			assert!(verify(&decoded).is_err());
+		}
+	}
+
+	#[test]
+	fn ethereum_signature_works() {
+		let key = SigningKey::from_slice(&[4; 32]).unwrap();
+		let account = ethereum_address(key.verifying_key());
+
+		// Immortal era, nonce and tip of `0`.
+		let extra = [0, 0, 0];
+		// `System.remark` with a remark that makes the payload longer than 256 bytes.
+		let call = (0u8, 0u8, vec![1u8; 300]).encode();
+		let additional_signed = (1u32, 2u32, [3u8; 32], [3u8; 32]).encode();
+
+		let payload = [&call[..], &extra, &additional_signed].concat();
+		let signature = sign_secp256k1(&key, &Keccak256::digest(blake2_256(&payload)));
+
+		let decoded = decode_signed(
+			"moonbeam_metadata_v15",
+			&account,
+			&signature,
+			&extra,
+			&call,
+			&additional_signed,
+		);
+		assert_eq!(Some(&payload[..]), decoded.signing_payload());
+		assert_eq!(Ok(SignatureScheme::Ethereum), verify(&decoded));
+
+		// Signing the unhashed payload isn't valid.
+		let signature = sign_secp256k1(&key, &Keccak256::digest(&payload));
+		let decoded = decode_signed(
+			"moonbeam_metadata_v15",
+			&account,
+			&signature,
+			&extra,
+			&call,
+			&additional_signed,
+		);
+		assert!(verify(&decoded).is_err());
+
+		// Plain signatures are only accepted as Ethereum signatures for Ethereum addresses.
+		let bytes = |len| Value::Sequence(vec![Value::Unsigned(1); len]);
+		for (account, signature) in [(32, 64), (32, 65), (20, 64)] {
+			assert_eq!(
+				Err("Unsupported signature scheme".into()),
+				verify_signature(&bytes(account), &bytes(signature), &payload)
+			);
+		}
+	}
+}
@@ -0,0 +1,167 @@
+//! RFC78 conformance test vectors.
+//!
+//! For every fixture a JSON file is stored in `fixtures/test_vectors`. It contains the SCALE
+//! encoded leaves with their hashes, the hashes of the inner nodes per level, the types tree
+//! root, the encoded extrinsic metadata, the digest and some sample extrinsics with their proofs.
+//! Other implementations can use these files to check that they are compatible.
+//!
+//! Run `UPDATE_TEST_VECTORS=1 cargo test test_vectors` to regenerate them.
+
+use std::fs;
+
+use codec::Encode;
+use serde_json::{json, Value};
+
+use crate::{
+	generate_metadata_digest, generate_proof_for_extrinsic, generate_type_information,
+	merkle_tree::tests::{TEST_ADDITIONAL_SIGNED, TEST_EXT},
+	tests::{load_metadata, FIXTURES},
+	ExtraInfo, MerkleTree, Proof, TypeInformation,
+};
+
+fn hex(bytes: impl AsRef<[u8]>) -> String {
+	array_bytes::bytes2hex("0x", bytes)
+}
+
+fn extra_info() -> ExtraInfo {
+	ExtraInfo {
+		spec_version: 1,
+		spec_name: "nice".into(),
+		base58_prefix: 1,
+		decimals: 1,
+		token_symbol: "lol".into(),
+	}
+}
+
+/// Returns an unsigned `System.remark` extrinsic.
+fn remark_extrinsic(type_information: &TypeInformation) -> Vec<u8> {
+	let call_ty = type_information.extrinsic_metadata.call_ty.id().unwrap();
+
+	let system = type_information
+		.types_by_id(call_ty)
+		.filter_map(|(_, ty)| ty.type_def.as_enumeration())
+		.find(|v| v.name == "System")
+		.unwrap();
+	let remark = type_information
+		.types_by_id(system.fields[0].ty.id().unwrap())
+		.filter_map(|(_, ty)| ty.type_def.as_enumeration())
+		.find(|v| v.name == "remark")
+		.unwrap();
+
+	let call = (system.index.0 as u8, remark.index.0 as u8, b"RFC78".to_vec());
+	(4u8, call).encode().encode()
+}
+
+fn proof_vector(proof: &Proof) -> Value {
+	json!({
+		"encoded": hex(proof.encode()),
+		"leaves": proof.leaves.iter().map(|l| hex(l.encode())).collect::<Vec<_>>(),
+		"leaf_indices": proof.leaf_indices,
+		"nodes": proof.nodes.iter().map(hex).collect::<Vec<_>>(),
+	})
+}
+
+/// Generate the test vector for the given `fixture`.
+fn test_vector(fixture: &str) -> Value {
+	let metadata = load_metadata(fixture);
+	let type_information = generate_type_information(&metadata).unwrap();
+	let merkle_tree = MerkleTree::new(type_information.types.clone());
+	let num_inner_nodes = merkle_tree.num_inner_nodes();
+
+	let leaves = type_information
+		.types
+		.values()
+		.zip(&merkle_tree.hashes[num_inner_nodes..])
+		.map(|(leaf, hash)| json!({ "encoded": hex(leaf.encode()), "hash": hex(hash) }))
+		.collect::<Vec<_>>();
+
+	let mut inner_nodes_per_level = Vec::new();
+	let mut level_start = 0;
+	while level_start < num_inner_nodes {
+		let level_end = (level_start * 2 + 1).min(num_inner_nodes);
+		inner_nodes_per_level
+			.push(merkle_tree.hashes[level_start..level_end].iter().map(hex).collect::<Vec<_>>());
+		level_start = level_start * 2 + 1;
+	}
+
+	let extra_info = extra_info();
+	let digest = generate_metadata_digest(&metadata, extra_info.clone()).unwrap();
+
+	let mut extrinsics =
+		vec![("System.remark (unsigned)", remark_extrinsic(&type_information), None)];
+	if fixture == "rococo_metadata_v15" {
+		extrinsics.push((
+			"Balances.transfer_keep_alive (signed)",
+			array_bytes::hex2bytes(TEST_EXT).unwrap(),
+			Some(array_bytes::hex2bytes(TEST_ADDITIONAL_SIGNED).unwrap()),
+		));
+	}
+
+	let extrinsics = extrinsics
+		.into_iter()
+		.map(|(description, extrinsic, additional_signed)| {
+			let proof =
+				generate_proof_for_extrinsic(&extrinsic, additional_signed.as_deref(), &metadata)
+					.unwrap();
+			assert_eq!(merkle_tree.root(), proof.root().unwrap());
+
+			json!({
+				"description": description,
+				"extrinsic": hex(&extrinsic),
+				"additional_signed": additional_signed.map(hex),
+				"proof": proof_vector(&proof),
+			})
+		})
+		.collect::<Vec<_>>();
+
+	json!({
+		"fixture": fixture,
+		"extra_info": {
+			"spec_version": extra_info.spec_version,
+			"spec_name": extra_info.spec_name,
+			"base58_prefix": extra_info.base58_prefix,
+			"decimals": extra_info.decimals,
+			"token_symbol": extra_info.token_symbol,
+		},
+		"leaves": leaves,
+		"inner_nodes_per_level": inner_nodes_per_level,
+		"types_tree_root": hex(merkle_tree.root()),
+		"extrinsic_metadata": {
+			"encoded": hex(type_information.extrinsic_metadata.encode()),
+			"hash": hex(type_information.extrinsic_metadata.hash()),
+		},
+		"metadata_digest": {
+			"encoded": hex(digest.encode()),
+			"hash": hex(digest.hash()),
+		},
+		"extrinsics": extrinsics,
+	})
+}
+
+#[test]
+fn test_vectors_are_up_to_date() {
+	let update = std::env::var("UPDATE_TEST_VECTORS").is_ok();
+
+	for (fixture, expected_hash) in FIXTURES {
+		let vector = test_vector(fixture);
+		assert_eq!(*expected_hash, vector["metadata_digest"]["hash"]);
+
+		let path = format!("{}/fixtures/test_vectors/{fixture}.json", env!("CARGO_MANIFEST_DIR"));
+		let vector = serde_json::to_string_pretty(&vector).unwrap() + "\n";
+
+		if update {
+			fs::write(&path, vector).unwrap();
+			continue;
+		}
+
+		let expected = fs::read_to_string(&path).unwrap_or_else(|_| {
+			panic!("`{path}` is missing, run with `UPDATE_TEST_VECTORS=1` to generate it")
+		});
+
+		// Not using `assert_eq!` as printing the vectors would flood the output.
+		assert!(
+			expected == vector,
+			"`{path}` is outdated, run with `UPDATE_TEST_VECTORS=1` to update it"
+		);
+	}
+}
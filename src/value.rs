@@ -0,0 +1,442 @@
+//! Decoding of values using the [`Type`](crate::types::Type)s of the merkle tree.
+
+use alloc::{
+	borrow::ToOwned,
+	format,
+	string::{String, ToString},
+	vec::Vec,
+};
+use core::cell::{Cell, RefCell};
+use scale_decode::{
+	visitor::{decode_with_visitor, DecodeAsTypeResult, DecodeError, DecodeItemIterator},
+	Visitor,
+};
+
+use crate::{
+	extrinsic_decoder::{decode_compact_u256, TypeResolver},
+	types::TypeRef,
+};
+
+/// The fields of a composite or of an enumeration variant.
+///
+/// Each field consists of the optional name of the field and its value.
+pub type Fields = Vec<(Option<String>, Value)>;
+
+/// A decoded value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+	Bool(bool),
+	Char(char),
+	Str(String),
+	/// An unsigned integer, compact encoded or not.
+	Unsigned(u128),
+	/// A signed integer.
+	Signed(i128),
+	/// An unsigned 256bit integer in little endian.
+	U256([u8; 32]),
+	/// A signed 256bit integer in little endian.
+	I256([u8; 32]),
+	/// A composite with its fields.
+	///
+	/// Also used for `Void`, as a composite without fields.
+	Composite(Fields),
+	/// A variant of an enumeration.
+	Variant {
+		name: String,
+		index: u8,
+		fields: Fields,
+	},
+	/// A sequence, an array or a tuple.
+	Sequence(Vec<Value>),
+	BitSequence(Vec<bool>),
+}
+
+impl Value {
+	/// Returns the value inside of composites with exactly one field.
+	///
+	/// This is done recursively, so `CheckNonce(Compact<u32>)` returns the nonce.
+	pub fn inner(&self) -> &Value {
+		match self {
+			Self::Composite(fields) if fields.len() == 1 => fields[0].1.inner(),
+			v => v,
+		}
+	}
+
+	/// Returns the value of the field with the given `name`.
+	///
+	/// Works for composites and enumeration variants.
+	pub fn field(&self, name: &str) -> Option<&Value> {
+		self.fields()?
+			.iter()
+			.find_map(|(n, v)| (n.as_deref() == Some(name)).then_some(v))
+	}
+
+	/// Returns the fields of a composite or an enumeration variant.
+	pub fn fields(&self) -> Option<&Fields> {
+		match self {
+			Self::Composite(fields) | Self::Variant { fields, .. } => Some(fields),
+			_ => None,
+		}
+	}
+
+	/// Returns the name of the variant, if this is an enumeration variant.
+	pub fn variant_name(&self) -> Option<&str> {
+		match self {
+			Self::Variant { name, .. } => Some(name),
+			_ => None,
+		}
+	}
+
+	/// Returns the unsigned integer.
+	pub fn as_unsigned(&self) -> Option<u128> {
+		match self {
+			Self::Unsigned(v) => Some(*v),
+			_ => None,
+		}
+	}
+
+	/// Returns the bytes of an account id.
+	///
+	/// Supports plain account ids and the `Id`, `Address20` and `Address32` variants of
+	/// `MultiAddress`.
+	pub fn as_account_id(&self) -> Option<Vec<u8>> {
+		match self {
+			Self::Variant { name, fields, .. } => match (name.as_str(), &fields[..]) {
+				("Id" | "Address20" | "Address32", [(_, value)]) => value.inner().as_bytes(),
+				_ => None,
+			},
+			v => v.inner().as_bytes(),
+		}
+	}
+
+	/// Returns the bytes, if this is a sequence of `u8`.
+	pub fn as_bytes(&self) -> Option<Vec<u8>> {
+		match self {
+			Self::Sequence(values) => values
+				.iter()
+				.map(|v| v.as_unsigned().and_then(|v| u8::try_from(v).ok()))
+				.collect(),
+			_ => None,
+		}
+	}
+}
+
+/// Decode a value of type `type_ref` from `input`.
+pub(crate) fn decode_value(
+	input: &mut &[u8],
+	type_ref: TypeRef,
+	type_resolver: &TypeResolver,
+) -> Result<Value, String> {
+	decode_with_visitor(input, type_ref, type_resolver, DecodeValue::default())
+		.map_err(|e| format!("{e}"))
+}
+
+/// Decode a call of type `call_ty` from `input` and collect it and all the calls nested in it.
+///
+/// The calls are returned in depth-first order, starting with the decoded call. Every call is
+/// decoded only once, nested calls are cloned from the values of the calls containing them.
+///
+/// Returns an error if any call is passed as opaque bytes or by its hash, e.g. as `DoubleEncoded`
+/// or `Bounded`, as these calls can not be collected.
+pub(crate) fn decode_calls(
+	input: &mut &[u8],
+	call_ty: TypeRef,
+	type_resolver: &TypeResolver,
+) -> Result<Vec<Value>, String> {
+	let collector = CallCollector {
+		call_ty,
+		calls: Default::default(),
+		entering_call: Cell::new(false),
+		opaque_call: Default::default(),
+	};
+
+	decode_with_visitor(input, call_ty, type_resolver, DecodeValue { calls: Some(&collector) })
+		.map_err(|e| format!("{e}"))?;
+
+	if let Some(path) = collector.opaque_call.into_inner() {
+		return Err(format!("Opaque call `{path}` can not be collected"))
+	}
+
+	Ok(collector
+		.calls
+		.into_inner()
+		.into_iter()
+		.map(|call| call.expect("All calls are decoded when decoding succeeds; qed"))
+		.collect())
+}
+
+/// Decode the `remaining` items of `items` as values.
+///
+/// See `decode_items` of the extrinsic decoder on why `decode_item` is required.
+fn decode_items<'scale, 'resolver>(
+	items: &mut impl DecodeItemIterator<'scale, 'resolver, TypeResolver>,
+	remaining: usize,
+	visitor: DecodeValue,
+) -> Result<Vec<Value>, DecodeError> {
+	(0..remaining)
+		.map(|_| items.decode_item(visitor).expect("There is at least one item remaining; qed"))
+		.collect()
+}
+
+/// Decode the `remaining` fields of `composite`, including their names.
+fn decode_fields<'scale, 'resolver>(
+	composite: &mut scale_decode::visitor::types::Composite<'scale, 'resolver, TypeResolver>,
+	visitor: DecodeValue,
+) -> Result<Fields, DecodeError> {
+	(0..composite.remaining())
+		.map(|_| {
+			let name = composite.peek_name().map(ToOwned::to_owned);
+			let value = composite
+				.decode_item(visitor)
+				.expect("There is at least one item remaining; qed")?;
+
+			Ok((name, value))
+		})
+		.collect()
+}
+
+/// Collects the calls while decoding, see [`decode_calls`].
+struct CallCollector {
+	call_ty: TypeRef,
+	/// The calls in depth-first order, `None` while a call is still being decoded.
+	calls: RefCell<Vec<Option<Value>>>,
+	/// Set when entering a call, to not collect the same call again.
+	entering_call: Cell<bool>,
+	/// The path of the first type found that holds a call as opaque bytes or by its hash.
+	opaque_call: RefCell<Option<String>>,
+}
+
+/// Returns `true` if the type at `path` holds a call as opaque bytes or by its hash.
+fn is_opaque_call(path: &[String]) -> bool {
+	match path {
+		[.., name] if name == "DoubleEncoded" || name == "WrapperKeepOpaque" => true,
+		[.., module, name] => module == "preimages" && name == "Bounded",
+		_ => false,
+	}
+}
+
+/// Visitor that decodes a [`Value`].
+///
+/// If `calls` is `Some(_)`, all values of the call type are collected as well.
+#[derive(Clone, Copy, Default)]
+struct DecodeValue<'a> {
+	calls: Option<&'a CallCollector>,
+}
+
+impl Visitor for DecodeValue<'_> {
+	type TypeResolver = TypeResolver;
+	type Value<'scale, 'resolver> = Value;
+	type Error = DecodeError;
+
+	fn unchecked_decode_as_type<'scale, 'resolver>(
+		self,
+		input: &mut &'scale [u8],
+		type_id: TypeRef,
+		types: &'resolver TypeResolver,
+	) -> DecodeAsTypeResult<Self, Result<Self::Value<'scale, 'resolver>, Self::Error>> {
+		if type_id == TypeRef::CompactU256 {
+			return DecodeAsTypeResult::Decoded(
+				decode_compact_u256(input).map(Value::U256).map_err(Into::into),
+			)
+		}
+
+		let Some(collector) = self.calls else { return DecodeAsTypeResult::Skipped(self) };
+
+		if let Some(path) = types.path(type_id).filter(|path| is_opaque_call(path)) {
+			collector.opaque_call.borrow_mut().get_or_insert_with(|| path.join("::"));
+		}
+
+		if type_id == collector.call_ty && !collector.entering_call.get() {
+			let index = {
+				let mut calls = collector.calls.borrow_mut();
+				calls.push(None);
+				calls.len() - 1
+			};
+
+			collector.entering_call.set(true);
+			let call = decode_with_visitor(input, type_id, types, self);
+			if let Ok(call) = &call {
+				collector.calls.borrow_mut()[index] = Some(call.clone());
+			}
+
+			DecodeAsTypeResult::Decoded(call)
+		} else {
+			collector.entering_call.set(false);
+			DecodeAsTypeResult::Skipped(self)
+		}
+	}
+
+	fn visit_bool<'scale, 'resolver>(
+		self,
+		value: bool,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(Value::Bool(value))
+	}
+
+	fn visit_char<'scale, 'resolver>(
+		self,
+		value: char,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(Value::Char(value))
+	}
+
+	fn visit_u8<'scale, 'resolver>(
+		self,
+		value: u8,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(Value::Unsigned(value.into()))
+	}
+
+	fn visit_u16<'scale, 'resolver>(
+		self,
+		value: u16,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(Value::Unsigned(value.into()))
+	}
+
+	fn visit_u32<'scale, 'resolver>(
+		self,
+		value: u32,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(Value::Unsigned(value.into()))
+	}
+
+	fn visit_u64<'scale, 'resolver>(
+		self,
+		value: u64,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(Value::Unsigned(value.into()))
+	}
+
+	fn visit_u128<'scale, 'resolver>(
+		self,
+		value: u128,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(Value::Unsigned(value))
+	}
+
+	fn visit_u256<'resolver>(
+		self,
+		value: &[u8; 32],
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'_, 'resolver>, Self::Error> {
+		Ok(Value::U256(*value))
+	}
+
+	fn visit_i8<'scale, 'resolver>(
+		self,
+		value: i8,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(Value::Signed(value.into()))
+	}
+
+	fn visit_i16<'scale, 'resolver>(
+		self,
+		value: i16,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(Value::Signed(value.into()))
+	}
+
+	fn visit_i32<'scale, 'resolver>(
+		self,
+		value: i32,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(Value::Signed(value.into()))
+	}
+
+	fn visit_i64<'scale, 'resolver>(
+		self,
+		value: i64,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(Value::Signed(value.into()))
+	}
+
+	fn visit_i128<'scale, 'resolver>(
+		self,
+		value: i128,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(Value::Signed(value))
+	}
+
+	fn visit_i256<'resolver>(
+		self,
+		value: &[u8; 32],
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'_, 'resolver>, Self::Error> {
+		Ok(Value::I256(*value))
+	}
+
+	fn visit_sequence<'scale, 'resolver>(
+		self,
+		value: &mut scale_decode::visitor::types::Sequence<'scale, 'resolver, Self::TypeResolver>,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		let remaining = value.remaining();
+		decode_items(value, remaining, self).map(Value::Sequence)
+	}
+
+	fn visit_composite<'scale, 'resolver>(
+		self,
+		value: &mut scale_decode::visitor::types::Composite<'scale, 'resolver, Self::TypeResolver>,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		decode_fields(value, self).map(Value::Composite)
+	}
+
+	fn visit_tuple<'scale, 'resolver>(
+		self,
+		value: &mut scale_decode::visitor::types::Tuple<'scale, 'resolver, Self::TypeResolver>,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		let remaining = value.remaining();
+		decode_items(value, remaining, self).map(Value::Sequence)
+	}
+
+	fn visit_str<'scale, 'resolver>(
+		self,
+		value: &mut scale_decode::visitor::types::Str<'scale>,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(Value::Str(value.as_str()?.to_string()))
+	}
+
+	fn visit_variant<'scale, 'resolver>(
+		self,
+		value: &mut scale_decode::visitor::types::Variant<'scale, 'resolver, Self::TypeResolver>,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		let name = value.name().to_string();
+		let index = value.index();
+
+		Ok(Value::Variant { name, index, fields: decode_fields(value.fields(), self)? })
+	}
+
+	fn visit_array<'scale, 'resolver>(
+		self,
+		value: &mut scale_decode::visitor::types::Array<'scale, 'resolver, Self::TypeResolver>,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		let remaining = value.remaining();
+		decode_items(value, remaining, self).map(Value::Sequence)
+	}
+
+	fn visit_bitsequence<'scale, 'resolver>(
+		self,
+		value: &mut scale_decode::visitor::types::BitSequence<'scale>,
+		_type_id: TypeRef,
+	) -> Result<Self::Value<'scale, 'resolver>, Self::Error> {
+		Ok(Value::BitSequence(value.decode()?.collect::<Result<_, _>>()?))
+	}
+}